@@ -0,0 +1,43 @@
+extern crate async_docker;
+extern crate http;
+extern crate futures;
+extern crate tokio;
+
+use async_docker::{DockerApi, new_docker, AttachOptions};
+use futures::{future, Future, Stream};
+use std::env;
+
+fn main() {
+    if env::args().count() < 3 {
+        println!("Too few arguments (<2).");
+        return;
+    }
+
+    let container = env::args().nth(1).unwrap();
+    let input = format!("{}\n", env::args().nth(2).unwrap());
+
+    let work = future::lazy(move || {
+        let docker: Box<DockerApi> = new_docker(None).unwrap();
+        let opts = AttachOptions::builder()
+            .stream(true)
+            .stdin(true)
+            .stdout(true)
+            .stderr(true)
+            .build();
+
+        docker
+            .container(container.into())
+            .attach(&opts)
+            .and_then(move |(stdin, output)| {
+                tokio::spawn(
+                    tokio::io::write_all(stdin, input)
+                        .map(|_| ())
+                        .map_err(|e| eprintln!("{:?}", e)),
+                );
+
+                output.for_each(|a| Ok(println!("{:?}", a)))
+            }).map_err(|e| eprintln!("{:?}", e))
+    });
+
+    tokio::runtime::run(work);
+}
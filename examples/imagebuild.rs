@@ -4,7 +4,7 @@ extern crate futures;
 extern crate tokio;
 
 use async_docker::{DockerApi, new_docker, BuildOptions};
-use futures::{future, Future};
+use futures::{future, Future, Stream};
 use std::env;
 
 fn main() {
@@ -23,7 +23,12 @@ fn main() {
         docker
             .images()
             .build(&opts)
-            .and_then(|a| Ok(println!("{:#?}", a)))
+            .for_each(|output| {
+                if let Some(stream) = output.stream {
+                    print!("{}", stream);
+                }
+                Ok(())
+            })
             .map_err(|e| eprintln!("{:?}", e))
     });
 
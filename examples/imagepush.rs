@@ -0,0 +1,42 @@
+extern crate async_docker;
+extern crate futures;
+extern crate tokio;
+
+use async_docker::{new_docker, DockerApi, PushOptions, RegistryAuth};
+use futures::{future, Future, Stream};
+use std::borrow::Cow;
+use std::env;
+
+fn main() {
+    let image = match env::args().nth(1) {
+        Some(val) => val,
+        None => {
+            println!("Not enough arguments");
+            return;
+        }
+    };
+
+    let mut opts = PushOptions::builder();
+    if let (Ok(username), Ok(password)) = (env::var("REGISTRY_USER"), env::var("REGISTRY_PASSWORD"))
+    {
+        opts.auth(
+            RegistryAuth::builder()
+                .username(username)
+                .password(password)
+                .build(),
+        );
+    }
+    let opts = opts.build();
+
+    let work = future::lazy(move || {
+        let docker: Box<DockerApi> = new_docker(None).unwrap();
+
+        docker
+            .image(Cow::from(image))
+            .push(&opts)
+            .for_each(|progress| Ok(println!("{:?}", progress)))
+            .map_err(|e| eprintln!("{:?}", e))
+    });
+
+    tokio::runtime::run(work);
+}
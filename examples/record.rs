@@ -0,0 +1,24 @@
+extern crate async_docker;
+extern crate futures;
+extern crate tokio;
+
+use async_docker::{new_docker_with_transport, DockerApi, DockerBuilder, RecordingTransport};
+use futures::{future, Future};
+use std::env;
+use std::sync::Arc;
+
+fn main() {
+    let path = env::args().nth(1).expect("Usage: cargo run --example record -- <cassette>");
+
+    let work = future::lazy(move || {
+        let transport = DockerBuilder::new().transport().unwrap();
+        let recording = RecordingTransport::new(transport, path);
+        let docker: Box<DockerApi> = new_docker_with_transport(Arc::new(recording));
+
+        docker
+            .info()
+            .then(|a| Ok(println!("{:?}", a)))
+    });
+
+    tokio::runtime::run(work);
+}
@@ -9,17 +9,40 @@ use self::serde_json::to_value as de_to_value;
 use self::serde_json::Number;
 use self::serde_json::Value;
 
+use base64;
+use futures::Stream;
+use hyper::Body;
+use hyper::Chunk;
 use std::cmp::Eq;
 use std::collections::{BTreeMap, HashMap};
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs;
 use std::hash::Hash;
+use std::io;
+use std::io::Read;
 use std::iter::Peekable;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 use url::form_urlencoded;
 
+use communicate::version::ApiVersion;
 use errors::Error;
 use errors::Result;
+use tarball::body;
+use tarball::dockerignore::Excludes;
+use tarball::tarball;
+use tarball::tarball::Format;
+
+/// Registry credentials without a server address are used for Docker Hub, known by
+/// this address in the registry config of builds
+const DEFAULT_REGISTRY: &str = "https://index.docker.io/v1/";
 
 #[derive(Default)]
 pub struct PullOptions {
+    auth: Option<RegistryAuth>,
     params: HashMap<&'static str, String>,
 }
 
@@ -37,10 +60,16 @@ impl PullOptions {
             Some(form_urlencoded::serialize(&self.params))
         }
     }
+
+    /// credentials sent in the `X-Registry-Auth` header
+    pub fn auth(&self) -> Option<&RegistryAuth> {
+        self.auth.as_ref()
+    }
 }
 
 #[derive(Default)]
 pub struct PullOptionsBuilder {
+    auth: Option<RegistryAuth>,
     params: HashMap<&'static str, String>,
 }
 
@@ -83,8 +112,213 @@ impl PullOptionsBuilder {
         self
     }
 
+    /// credentials for pulling from a private registry
+    pub fn auth(&mut self, auth: RegistryAuth) -> &mut PullOptionsBuilder {
+        self.auth = Some(auth);
+        self
+    }
+
     pub fn build(&self) -> PullOptions {
         PullOptions {
+            auth: self.auth.clone(),
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Credentials for a docker registry, either a username and password or an identity
+/// token obtained from a previous login
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+pub enum RegistryAuth {
+    Password {
+        username: String,
+        password: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        email: Option<String>,
+        #[serde(rename = "serveraddress")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        server_address: Option<String>,
+    },
+    Token {
+        #[serde(rename = "identitytoken")]
+        identity_token: String,
+    },
+}
+
+impl RegistryAuth {
+    /// return a new instance of a builder for password credentials
+    pub fn builder() -> RegistryAuthBuilder {
+        RegistryAuthBuilder::new()
+    }
+
+    /// credentials made of an identity token
+    pub fn token<S>(token: S) -> RegistryAuth
+    where
+        S: Into<String>,
+    {
+        RegistryAuth::Token {
+            identity_token: token.into(),
+        }
+    }
+
+    /// serialize credentials as the url safe base64 encoded JSON expected by
+    /// the `X-Registry-Auth` header
+    pub fn serialize(&self) -> String {
+        let json = ser_to_string(self).expect("RegistryAuth serialization failed");
+        base64::encode_config(&json, base64::URL_SAFE)
+    }
+
+    fn server_address(&self) -> Option<&str> {
+        match *self {
+            RegistryAuth::Password {
+                ref server_address, ..
+            } => server_address.as_deref(),
+            RegistryAuth::Token { .. } => None,
+        }
+    }
+}
+
+/// Keeps the password and identity token out of logs
+impl fmt::Debug for RegistryAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RegistryAuth::Password {
+                ref username,
+                ref email,
+                ref server_address,
+                ..
+            } => f
+                .debug_struct("Password")
+                .field("username", username)
+                .field("password", &"<redacted>")
+                .field("email", email)
+                .field("server_address", server_address)
+                .finish(),
+            RegistryAuth::Token { .. } => f
+                .debug_struct("Token")
+                .field("identity_token", &"<redacted>")
+                .finish(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RegistryAuthBuilder {
+    username: Option<String>,
+    password: Option<String>,
+    email: Option<String>,
+    server_address: Option<String>,
+}
+
+impl RegistryAuthBuilder {
+    pub fn new() -> RegistryAuthBuilder {
+        RegistryAuthBuilder {
+            ..Default::default()
+        }
+    }
+
+    pub fn username<U>(&mut self, username: U) -> &mut RegistryAuthBuilder
+    where
+        U: Into<String>,
+    {
+        self.username = Some(username.into());
+        self
+    }
+
+    pub fn password<P>(&mut self, password: P) -> &mut RegistryAuthBuilder
+    where
+        P: Into<String>,
+    {
+        self.password = Some(password.into());
+        self
+    }
+
+    pub fn email<E>(&mut self, email: E) -> &mut RegistryAuthBuilder
+    where
+        E: Into<String>,
+    {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// address of the registry, e.g. `registry.example.com:5000`
+    pub fn server_address<A>(&mut self, address: A) -> &mut RegistryAuthBuilder
+    where
+        A: Into<String>,
+    {
+        self.server_address = Some(address.into());
+        self
+    }
+
+    pub fn build(&self) -> RegistryAuth {
+        RegistryAuth::Password {
+            username: self.username.clone().unwrap_or_default(),
+            password: self.password.clone().unwrap_or_default(),
+            email: self.email.clone(),
+            server_address: self.server_address.clone(),
+        }
+    }
+}
+
+/// Interface for pushing an image to a registry
+#[derive(Default)]
+pub struct PushOptions {
+    auth: Option<RegistryAuth>,
+    params: HashMap<&'static str, String>,
+}
+
+impl PushOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> PushOptionsBuilder {
+        PushOptionsBuilder::new()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(form_urlencoded::serialize(&self.params))
+        }
+    }
+
+    /// credentials sent in the `X-Registry-Auth` header
+    pub fn auth(&self) -> Option<&RegistryAuth> {
+        self.auth.as_ref()
+    }
+}
+
+#[derive(Default)]
+pub struct PushOptionsBuilder {
+    auth: Option<RegistryAuth>,
+    params: HashMap<&'static str, String>,
+}
+
+impl PushOptionsBuilder {
+    pub fn new() -> PushOptionsBuilder {
+        PushOptionsBuilder {
+            ..Default::default()
+        }
+    }
+
+    /// push only this tag of the image, all of its tags are pushed by default
+    pub fn tag<T>(&mut self, t: T) -> &mut PushOptionsBuilder
+    where
+        T: Into<String>,
+    {
+        self.params.insert("tag", t.into());
+        self
+    }
+
+    pub fn auth(&mut self, auth: RegistryAuth) -> &mut PushOptionsBuilder {
+        self.auth = Some(auth);
+        self
+    }
+
+    pub fn build(&self) -> PushOptions {
+        PushOptions {
+            auth: self.auth.clone(),
             params: self.params.clone(),
         }
     }
@@ -93,7 +327,12 @@ impl PullOptionsBuilder {
 #[derive(Default)]
 pub struct BuildOptions {
     pub path: String,
+    auths: Vec<RegistryAuth>,
+    compression: Compression,
+    excludes: Vec<String>,
+    reproducible: bool,
     params: HashMap<&'static str, String>,
+    params_list: HashMap<&'static str, Vec<String>>,
 }
 
 impl BuildOptions {
@@ -109,18 +348,89 @@ impl BuildOptions {
 
     /// serialize options as a string. returns None if no options are defined
     pub fn serialize(&self) -> Option<String> {
-        if self.params.is_empty() {
-            None
+        if self.params.is_empty() && self.params_list.is_empty() {
+            return None;
+        }
+
+        let list = self
+            .params_list
+            .iter()
+            .flat_map(|(k, values)| values.iter().map(move |v| (*k, v.as_str())));
+        let params = self
+            .params
+            .iter()
+            .map(|(k, v)| (*k, v.as_str()))
+            .chain(list)
+            .collect::<Vec<_>>();
+
+        Some(form_urlencoded::serialize(params))
+    }
+
+    /// path of the Dockerfile within the build context
+    pub fn dockerfile(&self) -> &str {
+        self.params
+            .get("dockerfile")
+            .map_or("Dockerfile", String::as_str)
+    }
+
+    /// patterns excluded from directory contexts on top of their `.dockerignore`
+    pub fn excludes(&self) -> &[String] {
+        &self.excludes
+    }
+
+    /// compression of the archives generated from directory and in-memory contexts
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// whether the ownership and modification times of directory contexts are zeroed
+    pub fn reproducible(&self) -> bool {
+        self.reproducible
+    }
+
+    /// the lowest API version supporting every option that is set
+    pub(crate) fn required_api_version(&self) -> Option<(ApiVersion, &'static str)> {
+        if self.params.contains_key("platform") {
+            Some((ApiVersion::new(1, 32), "BuildOptionsBuilder::platform"))
+        } else if self.params.contains_key("target") {
+            Some((ApiVersion::new(1, 29), "BuildOptionsBuilder::target"))
         } else {
-            Some(form_urlencoded::serialize(&self.params))
+            None
+        }
+    }
+
+    /// serialize the registry credentials as the url safe base64 encoded JSON expected
+    /// by the `X-Registry-Config` header. returns None if no credentials are defined
+    pub fn registry_config(&self) -> Option<String> {
+        if self.auths.is_empty() {
+            return None;
         }
+
+        let config = self
+            .auths
+            .iter()
+            .map(|auth| {
+                let address = auth.server_address().unwrap_or(DEFAULT_REGISTRY);
+                (address, auth)
+            }).collect::<BTreeMap<_, _>>();
+        let json = ser_to_string(&config).expect("Registry config serialization failed");
+
+        Some(base64::encode_config(&json, base64::URL_SAFE))
     }
 }
 
 #[derive(Default)]
 pub struct BuildOptionsBuilder {
     path: String,
+    auths: Vec<RegistryAuth>,
+    compression: Compression,
+    excludes: Vec<String>,
+    reproducible: bool,
     params: HashMap<&'static str, String>,
+    params_list: HashMap<&'static str, Vec<String>>,
+    build_args: BTreeMap<String, String>,
+    labels: BTreeMap<String, String>,
+    cache_from: Vec<String>,
 }
 
 impl BuildOptionsBuilder {
@@ -178,6 +488,13 @@ impl BuildOptionsBuilder {
         self
     }
 
+    /// credentials for pulling base images from a private registry, keyed by their
+    /// server address. can be called once per registry
+    pub fn auth(&mut self, auth: RegistryAuth) -> &mut BuildOptionsBuilder {
+        self.auths.push(auth);
+        self
+    }
+
     /// `bridge`, `host`, `none`, `container:<name|id>`, or a custom network name.
     pub fn network_mode<T>(&mut self, t: T) -> &mut BuildOptionsBuilder
     where
@@ -187,22 +504,284 @@ impl BuildOptionsBuilder {
         self
     }
 
-    // todo: memory
-    // todo: memswap
-    // todo: cpushares
-    // todo: cpusetcpus
-    // todo: cpuperiod
-    // todo: cpuquota
-    // todo: buildargs
+    /// build-time variable consumed by an `ARG` instruction of the Dockerfile
+    pub fn build_arg<K, V>(&mut self, key: K, value: V) -> &mut BuildOptionsBuilder
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.build_args.insert(key.into(), value.into());
+        self
+    }
+
+    /// label to set on the built image
+    pub fn label<K, V>(&mut self, key: K, value: V) -> &mut BuildOptionsBuilder
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// build stage to stop at in a multi-stage Dockerfile. requires API 1.29
+    pub fn target<T>(&mut self, target: T) -> &mut BuildOptionsBuilder
+    where
+        T: Into<String>,
+    {
+        self.params.insert("target", target.into());
+        self
+    }
+
+    /// platform to build for, in the `os[/arch[/variant]]` format. requires API 1.32
+    pub fn platform<P>(&mut self, platform: P) -> &mut BuildOptionsBuilder
+    where
+        P: Into<String>,
+    {
+        self.params.insert("platform", platform.into());
+        self
+    }
+
+    /// attempt to pull the base images even if older ones exist locally
+    pub fn pull(&mut self, pull: bool) -> &mut BuildOptionsBuilder {
+        self.params.insert("pull", pull.to_string());
+        self
+    }
+
+    /// squash the resulting image layers into a single layer. requires an
+    /// experimental daemon
+    pub fn squash(&mut self, squash: bool) -> &mut BuildOptionsBuilder {
+        self.params.insert("squash", squash.to_string());
+        self
+    }
+
+    /// image used as a cache source. can be called once per image
+    pub fn cache_from<I>(&mut self, image: I) -> &mut BuildOptionsBuilder
+    where
+        I: Into<String>,
+    {
+        self.cache_from.push(image.into());
+        self
+    }
+
+    /// memory limit of the build containers, in bytes
+    pub fn memory(&mut self, memory: u64) -> &mut BuildOptionsBuilder {
+        self.params.insert("memory", memory.to_string());
+        self
+    }
+
+    /// total memory limit (memory + swap) of the build containers, in bytes.
+    /// `-1` enables unlimited swap
+    pub fn memswap(&mut self, memswap: i64) -> &mut BuildOptionsBuilder {
+        self.params.insert("memswap", memswap.to_string());
+        self
+    }
+
+    /// CPU shares, the relative weight of the build containers
+    pub fn cpu_shares(&mut self, cpu_shares: u32) -> &mut BuildOptionsBuilder {
+        self.params.insert("cpushares", cpu_shares.to_string());
+        self
+    }
+
+    /// CPUs the build containers are allowed to run on, e.g. `0-3` or `0,1`
+    pub fn cpuset_cpus<C>(&mut self, cpus: C) -> &mut BuildOptionsBuilder
+    where
+        C: Into<String>,
+    {
+        self.params.insert("cpusetcpus", cpus.into());
+        self
+    }
+
+    /// length of a CPU period, in microseconds
+    pub fn cpu_period(&mut self, cpu_period: u64) -> &mut BuildOptionsBuilder {
+        self.params.insert("cpuperiod", cpu_period.to_string());
+        self
+    }
+
+    /// microseconds of CPU time the build containers can get in a CPU period
+    pub fn cpu_quota(&mut self, cpu_quota: u64) -> &mut BuildOptionsBuilder {
+        self.params.insert("cpuquota", cpu_quota.to_string());
+        self
+    }
+
+    /// entry to add to the `/etc/hosts` of the build containers. can be called once
+    /// per host
+    pub fn extra_host<H, I>(&mut self, host: H, ip: I) -> &mut BuildOptionsBuilder
+    where
+        H: Into<String>,
+        I: Into<String>,
+    {
+        self.params_list
+            .entry("extrahosts")
+            .or_default()
+            .push(format!("{}:{}", host.into(), ip.into()));
+        self
+    }
+
+    /// leave the files matching the pattern out of a directory context, with the
+    /// `.dockerignore` syntax. can be called once per pattern, a pattern prefixed
+    /// with `!` re-includes files excluded by an earlier one
+    pub fn exclude<P>(&mut self, pattern: P) -> &mut BuildOptionsBuilder
+    where
+        P: Into<String>,
+    {
+        self.excludes.push(pattern.into());
+        self
+    }
+
+    /// compression of the archives generated from directory and in-memory contexts.
+    /// defaults to none, which is the fastest when the daemon is local
+    pub fn compression(&mut self, compression: Compression) -> &mut BuildOptionsBuilder {
+        self.compression = compression;
+        self
+    }
+
+    /// zero the ownership and modification times of the files of a directory context,
+    /// so that identical files produce an identical context and hit the build cache
+    pub fn reproducible(&mut self, reproducible: bool) -> &mut BuildOptionsBuilder {
+        self.reproducible = reproducible;
+        self
+    }
+
+    /// size of `/dev/shm` of the build containers, in bytes
+    pub fn shm_size(&mut self, shm_size: u64) -> &mut BuildOptionsBuilder {
+        self.params.insert("shmsize", shm_size.to_string());
+        self
+    }
 
     pub fn build(&self) -> BuildOptions {
+        let mut params = self.params.clone();
+
+        if !self.build_args.is_empty() {
+            let args = ser_to_string(&self.build_args).expect("Build args serialization failed");
+            params.insert("buildargs", args);
+        }
+        if !self.labels.is_empty() {
+            let labels = ser_to_string(&self.labels).expect("Labels serialization failed");
+            params.insert("labels", labels);
+        }
+        if !self.cache_from.is_empty() {
+            let images = ser_to_string(&self.cache_from).expect("Cache serialization failed");
+            params.insert("cachefrom", images);
+        }
+
         BuildOptions {
             path: self.path.clone(),
-            params: self.params.clone(),
+            auths: self.auths.clone(),
+            compression: self.compression,
+            excludes: self.excludes.clone(),
+            reproducible: self.reproducible,
+            params,
+            params_list: self.params_list.clone(),
         }
     }
 }
 
+/// Compression of the tar archives generated for the daemon
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    /// gzip, optimized for speed
+    Fast,
+    /// gzip, optimized for size
+    Best,
+}
+
+/// Files sent to the daemon as the context of an image build
+pub enum BuildContext {
+    /// Directory on the local filesystem, archived before being sent without the
+    /// files matching its `.dockerignore`
+    Directory(PathBuf),
+    /// Tar archive, optionally gzip compressed
+    Tar(Body),
+    /// In-memory files keyed by their path in the context, readable by everyone
+    Files(BTreeMap<String, Vec<u8>>),
+}
+
+impl BuildContext {
+    pub fn directory<P>(path: P) -> BuildContext
+    where
+        P: Into<PathBuf>,
+    {
+        BuildContext::Directory(path.into())
+    }
+
+    /// Tar archive held in memory
+    pub fn tar<B>(tar: B) -> BuildContext
+    where
+        B: Into<Body>,
+    {
+        BuildContext::Tar(tar.into())
+    }
+
+    /// Tar archive sent chunk by chunk as the stream produces it
+    pub fn tar_stream<S>(stream: S) -> BuildContext
+    where
+        S: Stream + Send + 'static,
+        S::Error: Into<Box<StdError + Send + Sync>>,
+        Chunk: From<S::Item>,
+    {
+        BuildContext::Tar(Body::wrap_stream(stream))
+    }
+
+    /// Tar archive read on a separate thread as the daemon consumes it
+    pub fn tar_reader<R>(mut reader: R) -> BuildContext
+    where
+        R: Read + Send + 'static,
+    {
+        BuildContext::Tar(body::body(move |writer| {
+            io::copy(&mut reader, writer)?;
+            Ok(())
+        }))
+    }
+
+    /// Files held in memory, keyed by their path in the context
+    pub fn files<I, P, C>(files: I) -> BuildContext
+    where
+        I: IntoIterator<Item = (P, C)>,
+        P: Into<String>,
+        C: Into<Vec<u8>>,
+    {
+        let files = files
+            .into_iter()
+            .map(|(path, content)| (path.into(), content.into()))
+            .collect();
+
+        BuildContext::Files(files)
+    }
+
+    /// Request body carrying the context as a tar archive, generated lazily while
+    /// it is sent
+    pub(crate) fn into_body(self, opts: &BuildOptions) -> Result<Body> {
+        let format = Format {
+            compression: opts.compression(),
+            reproducible: opts.reproducible(),
+        };
+
+        let body = match self {
+            BuildContext::Directory(path) => {
+                // Fail before sending anything when the context can't be archived
+                fs::metadata(&path)?;
+                let excludes =
+                    Excludes::for_build_context(&path, opts.dockerfile(), opts.excludes())?;
+                body::body(move |writer| {
+                    tarball::dir_excluding(writer, path, &excludes, format)
+                })
+            }
+            BuildContext::Files(files) => body::body(move |writer| {
+                let files = files
+                    .iter()
+                    .map(|(path, content)| (path.as_str(), 0o644, content.as_slice()));
+                tarball::files(writer, files, format)
+            }),
+            BuildContext::Tar(body) => body,
+        };
+
+        Ok(body)
+    }
+}
+
 /// Options for filtering container list results
 #[derive(Default)]
 pub struct ContainerListOptions {
@@ -342,7 +921,14 @@ impl ContainerOptions {
 
     /// serialize options as a string. returns None if no options are defined
     pub fn serialize(&self) -> Result<String> {
-        Ok(ser_to_string(&de_to_value(self)?)?)
+        let mut body = Value::Object(Map::new());
+        body["HostConfig"] = Value::Object(Map::new());
+
+        self.parse_from(&self.params, &mut body);
+        self.parse_from(&self.params_list, &mut body);
+        self.parse_from(&self.params_hash, &mut body);
+
+        Ok(ser_to_string(&body)?)
     }
 
     pub fn parse_from<'a, K, V>(&self, params: &'a HashMap<K, V>, body: &mut Value)
@@ -516,6 +1102,167 @@ impl ContainerOptionsBuilder {
         self
     }
 
+    /// exposes `port` of the container, `protocol` being `tcp`, `udp` or `sctp`
+    pub fn expose(&mut self, port: u16, protocol: &str) -> &mut ContainerOptionsBuilder {
+        self.object("ExposedPorts")
+            .insert(format!("{}/{}", port, protocol), Value::Object(Map::new()));
+        self
+    }
+
+    /// exposes `port` of the container and publishes it on `host_ip`, every interface
+    /// when empty, and `host_port`, an ephemeral port picked by the daemon when None.
+    /// A port can be published more than once.
+    pub fn publish(
+        &mut self,
+        port: u16,
+        protocol: &str,
+        host_ip: &str,
+        host_port: Option<u16>,
+    ) -> &mut ContainerOptionsBuilder {
+        self.expose(port, protocol);
+
+        let mut binding = Map::new();
+        binding.insert("HostIp".to_string(), Value::String(host_ip.to_owned()));
+        let host_port = host_port.map(|p| p.to_string()).unwrap_or_default();
+        binding.insert("HostPort".to_string(), Value::String(host_port));
+
+        let bindings = self
+            .object("HostConfig.PortBindings")
+            .entry(format!("{}/{}", port, protocol))
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(ref mut bindings) = *bindings {
+            bindings.push(Value::Object(binding));
+        }
+        self
+    }
+
+    /// hard memory limit in bytes
+    pub fn memory(&mut self, bytes: u64) -> &mut ContainerOptionsBuilder {
+        self.params
+            .insert("HostConfig.Memory", Value::Number(Number::from(bytes)));
+        self
+    }
+
+    /// memory plus swap limit in bytes, -1 for unlimited swap
+    pub fn memory_swap(&mut self, bytes: i64) -> &mut ContainerOptionsBuilder {
+        self.params
+            .insert("HostConfig.MemorySwap", Value::Number(Number::from(bytes)));
+        self
+    }
+
+    /// soft memory limit in bytes, enforced when the host runs low on memory
+    pub fn memory_reservation(&mut self, bytes: u64) -> &mut ContainerOptionsBuilder {
+        let k = "HostConfig.MemoryReservation";
+        self.params.insert(k, Value::Number(Number::from(bytes)));
+        self
+    }
+
+    /// CPU quota in billionths of a CPU, `1_500_000_000` allowing one and a half CPUs
+    pub fn nano_cpus(&mut self, nano_cpus: u64) -> &mut ContainerOptionsBuilder {
+        self.params.insert(
+            "HostConfig.NanoCpus",
+            Value::Number(Number::from(nano_cpus)),
+        );
+        self
+    }
+
+    /// CPU shares relative to the other containers, 1024 by default
+    pub fn cpu_shares(&mut self, shares: u64) -> &mut ContainerOptionsBuilder {
+        self.params
+            .insert("HostConfig.CpuShares", Value::Number(Number::from(shares)));
+        self
+    }
+
+    /// microseconds of CPU time the container can use per CPU period
+    pub fn cpu_quota(&mut self, microseconds: i64) -> &mut ContainerOptionsBuilder {
+        self.params.insert(
+            "HostConfig.CpuQuota",
+            Value::Number(Number::from(microseconds)),
+        );
+        self
+    }
+
+    /// length of a CPU period in microseconds
+    pub fn cpu_period(&mut self, microseconds: u64) -> &mut ContainerOptionsBuilder {
+        self.params.insert(
+            "HostConfig.CpuPeriod",
+            Value::Number(Number::from(microseconds)),
+        );
+        self
+    }
+
+    /// CPUs the container may run on, such as `0-3` or `0,1`
+    pub fn cpuset_cpus(&mut self, cpus: &str) -> &mut ContainerOptionsBuilder {
+        self.params
+            .insert("HostConfig.CpusetCpus", Value::String(cpus.to_owned()));
+        self
+    }
+
+    /// maximum number of processes, -1 for unlimited
+    pub fn pids_limit(&mut self, limit: i64) -> &mut ContainerOptionsBuilder {
+        self.params
+            .insert("HostConfig.PidsLimit", Value::Number(Number::from(limit)));
+        self
+    }
+
+    /// relative block IO weight, between 10 and 1000
+    pub fn blkio_weight(&mut self, weight: u16) -> &mut ContainerOptionsBuilder {
+        self.params.insert(
+            "HostConfig.BlkioWeight",
+            Value::Number(Number::from(weight)),
+        );
+        self
+    }
+
+    /// sets the soft and hard limits of the `name` resource, such as `nofile`
+    pub fn ulimit(&mut self, name: &str, soft: i64, hard: i64) -> &mut ContainerOptionsBuilder {
+        let mut ulimit = Map::new();
+        ulimit.insert("Name".to_string(), Value::String(name.to_owned()));
+        ulimit.insert("Soft".to_string(), Value::Number(Number::from(soft)));
+        ulimit.insert("Hard".to_string(), Value::Number(Number::from(hard)));
+
+        let ulimits = self
+            .params
+            .entry("HostConfig.Ulimits")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(ref mut ulimits) = *ulimits {
+            ulimits.push(Value::Object(ulimit));
+        }
+        self
+    }
+
+    /// size of `/dev/shm` in bytes
+    pub fn shm_size(&mut self, bytes: u64) -> &mut ContainerOptionsBuilder {
+        self.params
+            .insert("HostConfig.ShmSize", Value::Number(Number::from(bytes)));
+        self
+    }
+
+    /// keep the kernel from killing the container processes when it runs out of
+    /// memory, only allowed along with a memory limit
+    pub fn oom_kill_disable(&mut self, disable: bool) -> &mut ContainerOptionsBuilder {
+        self.params
+            .insert("HostConfig.OomKillDisable", Value::Bool(disable));
+        self
+    }
+
+    /// cgroup the container cgroups are created below
+    pub fn cgroup_parent(&mut self, parent: &str) -> &mut ContainerOptionsBuilder {
+        if !parent.is_empty() {
+            self.params
+                .insert("HostConfig.CgroupParent", Value::String(parent.to_owned()));
+        }
+        self
+    }
+
+    fn object(&mut self, key: &'static str) -> &mut Map<String, Value> {
+        self.params
+            .entry(key)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .expect("Parameter is not an object")
+    }
+
     pub fn build(&self) -> ContainerOptions {
         ContainerOptions {
             name: self.name.clone(),
@@ -532,6 +1279,8 @@ pub struct ExecContainerOptions {
     params: HashMap<&'static str, Vec<String>>,
     #[serde(flatten)]
     params_bool: HashMap<&'static str, bool>,
+    #[serde(flatten)]
+    params_str: HashMap<&'static str, String>,
 }
 
 impl ExecContainerOptions {
@@ -545,12 +1294,27 @@ impl ExecContainerOptions {
         let a = ser_to_string(&self).map_err(Error::from);
         Some(a.expect("Exec options serialization failed"))
     }
+
+    /// whether the exec instance allocates a TTY, in which case its output isn't multiplexed
+    pub fn tty(&self) -> bool {
+        self.params_bool.get("Tty").cloned().unwrap_or(false)
+    }
+
+    /// the lowest API version supporting every option that is set
+    pub(crate) fn required_api_version(&self) -> Option<(ApiVersion, &'static str)> {
+        if self.params_str.contains_key("WorkingDir") {
+            Some((ApiVersion::new(1, 35), "ExecContainerOptions::working_dir"))
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct ExecContainerOptionsBuilder {
     params: HashMap<&'static str, Vec<String>>,
     params_bool: HashMap<&'static str, bool>,
+    params_str: HashMap<&'static str, String>,
 }
 
 impl ExecContainerOptionsBuilder {
@@ -558,6 +1322,7 @@ impl ExecContainerOptionsBuilder {
         ExecContainerOptionsBuilder {
             params: HashMap::new(),
             params_bool: HashMap::new(),
+            params_str: HashMap::new(),
         }
     }
 
@@ -595,10 +1360,49 @@ impl ExecContainerOptionsBuilder {
         self
     }
 
+    /// Attach to stdin of the exec command
+    pub fn attach_stdin(&mut self, stdin: bool) -> &mut ExecContainerOptionsBuilder {
+        self.params_bool.insert("AttachStdin", stdin);
+        self
+    }
+
+    /// Allocate a pseudo-TTY for the exec command
+    pub fn tty(&mut self, tty: bool) -> &mut ExecContainerOptionsBuilder {
+        self.params_bool.insert("Tty", tty);
+        self
+    }
+
+    /// Run the exec command with extended privileges
+    pub fn privileged(&mut self, privileged: bool) -> &mut ExecContainerOptionsBuilder {
+        self.params_bool.insert("Privileged", privileged);
+        self
+    }
+
+    /// The user, and optionally, group to run the exec command as, in the
+    /// format `user`, `user:group`, `uid` or `uid:gid`
+    pub fn user(&mut self, user: &str) -> &mut ExecContainerOptionsBuilder {
+        self.params_str.insert("User", user.to_owned());
+        self
+    }
+
+    /// The working directory for the exec command inside the container
+    pub fn working_dir(&mut self, dir: &str) -> &mut ExecContainerOptionsBuilder {
+        self.params_str.insert("WorkingDir", dir.to_owned());
+        self
+    }
+
+    /// Key sequence for detaching, in the format `<char>` or `ctrl-<value>`,
+    /// comma separated
+    pub fn detach_keys(&mut self, keys: &str) -> &mut ExecContainerOptionsBuilder {
+        self.params_str.insert("DetachKeys", keys.to_owned());
+        self
+    }
+
     pub fn build(&self) -> ExecContainerOptions {
         ExecContainerOptions {
             params: self.params.clone(),
             params_bool: self.params_bool.clone(),
+            params_str: self.params_str.clone(),
         }
     }
 }
@@ -607,6 +1411,10 @@ impl ExecContainerOptionsBuilder {
 pub struct ContainerArchivePutOptions {
     #[serde(skip)]
     pub local_path: String,
+    #[serde(skip)]
+    pub compression: Compression,
+    #[serde(skip)]
+    pub reproducible: bool,
     #[serde(flatten)]
     params: HashMap<&'static str, String>,
     #[serde(flatten)]
@@ -621,10 +1429,31 @@ impl ContainerArchivePutOptions {
 
     /// serialize options as a string. returns None if no options are defined
     pub fn serialize(&self) -> Option<String> {
-        if self.params.is_empty() {
-            None
+        if self.params.is_empty() && self.params_bool.is_empty() {
+            return None;
+        }
+
+        let flags = self.params_bool.iter().map(|(k, v)| (*k, v.to_string()));
+        let mut params = self
+            .params
+            .iter()
+            .map(|(k, v)| (*k, v.clone()))
+            .chain(flags)
+            .collect::<Vec<_>>();
+        params.sort();
+
+        Some(form_urlencoded::serialize(params))
+    }
+
+    /// the lowest API version supporting every option that is set
+    pub(crate) fn required_api_version(&self) -> Option<(ApiVersion, &'static str)> {
+        if self.params_bool.get("copyUIDGID") == Some(&true) {
+            Some((
+                ApiVersion::new(1, 40),
+                "ContainerArchiveOptionsBuilder::copy_uid_gid",
+            ))
         } else {
-            Some(form_urlencoded::serialize(&self.params))
+            None
         }
     }
 }
@@ -632,6 +1461,8 @@ impl ContainerArchivePutOptions {
 #[derive(Default)]
 pub struct ContainerArchiveOptionsBuilder {
     local_path: String,
+    compression: Compression,
+    reproducible: bool,
     params: HashMap<&'static str, String>,
     params_bool: HashMap<&'static str, bool>,
 }
@@ -640,6 +1471,8 @@ impl ContainerArchiveOptionsBuilder {
     pub fn new() -> ContainerArchiveOptionsBuilder {
         ContainerArchiveOptionsBuilder {
             local_path: String::new(),
+            compression: Compression::None,
+            reproducible: false,
             params: HashMap::new(),
             params_bool: HashMap::new(),
         }
@@ -655,14 +1488,40 @@ impl ContainerArchiveOptionsBuilder {
         self
     }
 
-    pub fn no_overwrite(&mut self, o: bool) -> &mut ContainerArchiveOptionsBuilder {
-        self.params_bool.insert("noOverwriteDirNonDir", o);
+    /// compression of the archive generated from the local path, none by default
+    pub fn compression(
+        &mut self,
+        compression: Compression,
+    ) -> &mut ContainerArchiveOptionsBuilder {
+        self.compression = compression;
+        self
+    }
+
+    /// zero the ownership and modification times of the archived files
+    pub fn reproducible(&mut self, reproducible: bool) -> &mut ContainerArchiveOptionsBuilder {
+        self.reproducible = reproducible;
+        self
+    }
+
+    /// fail instead of replacing an existing directory with a non-directory, or the
+    /// other way around
+    pub fn no_overwrite(&mut self, o: bool) -> &mut ContainerArchiveOptionsBuilder {
+        self.params_bool.insert("noOverwriteDirNonDir", o);
+        self
+    }
+
+    /// give the copied files the user and group of the container instead of the
+    /// ownership recorded in the archive, requires API 1.40
+    pub fn copy_uid_gid(&mut self, copy: bool) -> &mut ContainerArchiveOptionsBuilder {
+        self.params_bool.insert("copyUIDGID", copy);
         self
     }
 
     pub fn build(&self) -> ContainerArchivePutOptions {
         ContainerArchivePutOptions {
             local_path: self.local_path.clone(),
+            compression: self.compression,
+            reproducible: self.reproducible,
             params: self.params.clone(),
             params_bool: self.params_bool.clone(),
         }
@@ -809,6 +1668,7 @@ impl EventsOptionsBuilder {
 /// Options for controlling log request results
 #[derive(Default)]
 pub struct LogsOptions {
+    pub tty: Option<bool>,
     params: HashMap<&'static str, String>,
 }
 
@@ -826,11 +1686,26 @@ impl LogsOptions {
             Some(form_urlencoded::serialize(&self.params))
         }
     }
+
+    /// whether every log line is prefixed with a timestamp
+    pub fn timestamps(&self) -> bool {
+        self.params.get("timestamps").map(String::as_str) == Some("true")
+    }
+
+    /// the lowest API version supporting every option that is set
+    pub(crate) fn required_api_version(&self) -> Option<(ApiVersion, &'static str)> {
+        if self.params.contains_key("until") {
+            Some((ApiVersion::new(1, 35), "LogsOptions::until"))
+        } else {
+            None
+        }
+    }
 }
 
 /// Builder interface for `LogsOptions`
 #[derive(Default)]
 pub struct LogsOptionsBuilder {
+    tty: Option<bool>,
     params: HashMap<&'static str, String>,
 }
 
@@ -861,19 +1736,148 @@ impl LogsOptionsBuilder {
         self
     }
 
+    /// Only return logs written at or after the given time
+    pub fn since(&mut self, time: SystemTime) -> &mut LogsOptionsBuilder {
+        self.params.insert("since", unix_timestamp(time));
+        self
+    }
+
+    /// Only return logs written within the given duration before now, all of them
+    /// when it reaches before the epoch
+    pub fn since_ago(&mut self, ago: Duration) -> &mut LogsOptionsBuilder {
+        self.since(SystemTime::now().checked_sub(ago).unwrap_or(UNIX_EPOCH))
+    }
+
+    /// Only return logs written before the given time
+    pub fn until(&mut self, time: SystemTime) -> &mut LogsOptionsBuilder {
+        self.params.insert("until", unix_timestamp(time));
+        self
+    }
+
+    /// Only return logs written earlier than the given duration before now
+    pub fn until_ago(&mut self, ago: Duration) -> &mut LogsOptionsBuilder {
+        self.until(SystemTime::now().checked_sub(ago).unwrap_or(UNIX_EPOCH))
+    }
+
+    /// Show extra attributes provided by the log driver
+    pub fn details(&mut self, d: bool) -> &mut LogsOptionsBuilder {
+        self.params.insert("details", d.to_string());
+        self
+    }
+
     /// how_many can either by "all" or a to_string() of the number
     pub fn tail(&mut self, how_many: &str) -> &mut LogsOptionsBuilder {
         self.params.insert("tail", how_many.to_owned());
         self
     }
 
+    /// Whether the container was created with a TTY, in which case its logs
+    /// aren't multiplexed. When not set, it is looked up by inspecting the container
+    pub fn tty(&mut self, t: bool) -> &mut LogsOptionsBuilder {
+        self.tty = Some(t);
+        self
+    }
+
     pub fn build(&self) -> LogsOptions {
         LogsOptions {
+            tty: self.tty,
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Options for attaching to a container
+#[derive(Default)]
+pub struct AttachOptions {
+    pub tty: bool,
+    params: HashMap<&'static str, String>,
+}
+
+impl AttachOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> AttachOptionsBuilder {
+        AttachOptionsBuilder::new()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(form_urlencoded::serialize(&self.params))
+        }
+    }
+}
+
+/// Builder interface for `AttachOptions`
+#[derive(Default)]
+pub struct AttachOptionsBuilder {
+    tty: bool,
+    params: HashMap<&'static str, String>,
+}
+
+impl AttachOptionsBuilder {
+    pub fn new() -> AttachOptionsBuilder {
+        AttachOptionsBuilder {
+            ..Default::default()
+        }
+    }
+
+    /// Replay the container's previous output before streaming
+    pub fn logs(&mut self, l: bool) -> &mut AttachOptionsBuilder {
+        self.params.insert("logs", l.to_string());
+        self
+    }
+
+    /// Keep streaming output until the container exits or the client detaches
+    pub fn stream(&mut self, s: bool) -> &mut AttachOptionsBuilder {
+        self.params.insert("stream", s.to_string());
+        self
+    }
+
+    pub fn stdin(&mut self, s: bool) -> &mut AttachOptionsBuilder {
+        self.params.insert("stdin", s.to_string());
+        self
+    }
+
+    pub fn stdout(&mut self, s: bool) -> &mut AttachOptionsBuilder {
+        self.params.insert("stdout", s.to_string());
+        self
+    }
+
+    pub fn stderr(&mut self, s: bool) -> &mut AttachOptionsBuilder {
+        self.params.insert("stderr", s.to_string());
+        self
+    }
+
+    /// Key sequence for detaching, in the format `<char>` or `ctrl-<value>`,
+    /// comma separated
+    pub fn detach_keys(&mut self, keys: &str) -> &mut AttachOptionsBuilder {
+        self.params.insert("detachKeys", keys.to_owned());
+        self
+    }
+
+    /// Whether the container was created with a TTY. Output of such containers
+    /// isn't multiplexed and is reported as stdout
+    pub fn tty(&mut self, t: bool) -> &mut AttachOptionsBuilder {
+        self.tty = t;
+        self
+    }
+
+    pub fn build(&self) -> AttachOptions {
+        AttachOptions {
+            tty: self.tty,
             params: self.params.clone(),
         }
     }
 }
 
+/// Formats a point in time as the fractional unix timestamp expected by the API
+fn unix_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{}.{:09}", since_epoch.as_secs(), since_epoch.subsec_nanos())
+}
+
 /// Filter options for image listings
 pub enum ImageFilter {
     Dangling,
@@ -1108,6 +2112,7 @@ impl NetworkCreateOptionsBuilder {
 /// Interface for connect container to network
 #[derive(Serialize)]
 pub struct ContainerConnectionOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub container: Option<String>,
     #[serde(flatten)]
     params: HashMap<&'static str, String>,
@@ -1153,9 +2158,176 @@ impl ContainerConnectionOptions {
     }
 }
 
+/// Filter options for volume listings
+pub enum VolumeFilter {
+    Dangling(bool),
+    Driver(String),
+    LabelName(String),
+    Label(String, String),
+    Name(String),
+}
+
+/// Options for filtering volume list results
+#[derive(Default)]
+pub struct VolumeListOptions {
+    params: HashMap<&'static str, String>,
+}
+
+impl VolumeListOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> VolumeListOptionsBuilder {
+        VolumeListOptionsBuilder::new()
+    }
+
+    /// serialize options as a string. returns None if no options are defined
+    pub fn serialize(&self) -> Option<String> {
+        if self.params.is_empty() {
+            None
+        } else {
+            Some(form_urlencoded::serialize(&self.params))
+        }
+    }
+}
+
+/// Builder interface for `VolumeListOptions`
+#[derive(Default)]
+pub struct VolumeListOptionsBuilder {
+    params: HashMap<&'static str, String>,
+}
+
+impl VolumeListOptionsBuilder {
+    pub fn new() -> VolumeListOptionsBuilder {
+        VolumeListOptionsBuilder {
+            ..Default::default()
+        }
+    }
+
+    pub fn filter(&mut self, filters: Vec<VolumeFilter>) -> &mut VolumeListOptionsBuilder {
+        let mut param: HashMap<&'static str, Vec<String>> = HashMap::new();
+        for f in filters {
+            let (key, value) = match f {
+                VolumeFilter::Dangling(d) => ("dangling", d.to_string()),
+                VolumeFilter::Driver(d) => ("driver", d),
+                VolumeFilter::LabelName(n) => ("label", n),
+                VolumeFilter::Label(n, v) => ("label", format!("{}={}", n, v)),
+                VolumeFilter::Name(n) => ("name", n),
+            };
+            param.entry(key).or_insert(Vec::new()).push(value);
+        }
+        // structure is a a json encoded object mapping string keys to a list
+        // of string values
+        self.params.insert(
+            "filters",
+            ser_to_string(&param).expect("Filter args serialization failed"),
+        );
+        self
+    }
+
+    pub fn build(&self) -> VolumeListOptions {
+        VolumeListOptions {
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// Interface for creating new docker volume
+#[derive(Serialize)]
+pub struct VolumeCreateOptions {
+    #[serde(flatten)]
+    params: HashMap<&'static str, String>,
+    #[serde(flatten)]
+    params_hash: HashMap<&'static str, HashMap<String, String>>,
+}
+
+impl VolumeCreateOptions {
+    /// return a new instance of a builder for options
+    pub fn builder() -> VolumeCreateOptionsBuilder {
+        VolumeCreateOptionsBuilder::new()
+    }
+
+    /// serialize options as a string
+    pub fn serialize(&self) -> Result<String> {
+        ser_to_string(&self).map_err(Error::from)
+    }
+}
+
+#[derive(Default)]
+pub struct VolumeCreateOptionsBuilder {
+    params: HashMap<&'static str, String>,
+    params_hash: HashMap<&'static str, HashMap<String, String>>,
+}
+
+impl VolumeCreateOptionsBuilder {
+    pub fn new() -> VolumeCreateOptionsBuilder {
+        VolumeCreateOptionsBuilder {
+            ..Default::default()
+        }
+    }
+
+    /// The new volume's name. If not specified, Docker generates a name
+    pub fn name(&mut self, name: &str) -> &mut VolumeCreateOptionsBuilder {
+        self.params.insert("Name", name.to_owned());
+        self
+    }
+
+    /// Name of the volume driver to use
+    pub fn driver(&mut self, name: &str) -> &mut VolumeCreateOptionsBuilder {
+        if !name.is_empty() {
+            self.params.insert("Driver", name.to_owned());
+        }
+        self
+    }
+
+    /// A driver specific option
+    pub fn driver_opt(&mut self, key: &str, value: &str) -> &mut VolumeCreateOptionsBuilder {
+        self.params_hash
+            .entry("DriverOpts")
+            .or_insert(HashMap::new())
+            .insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    /// User-defined key/value metadata
+    pub fn label(&mut self, key: &str, value: &str) -> &mut VolumeCreateOptionsBuilder {
+        self.params_hash
+            .entry("Labels")
+            .or_insert(HashMap::new())
+            .insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    pub fn build(&self) -> VolumeCreateOptions {
+        VolumeCreateOptions {
+            params: self.params.clone(),
+            params_hash: self.params_hash.clone(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::base64;
+    use super::de_to_value;
+    use super::form_urlencoded;
+    use super::ser_to_string;
+    use super::BuildContext;
+    use super::BuildOptionsBuilder;
+    use super::Compression;
     use super::ContainerOptionsBuilder;
+    use super::ExecContainerOptionsBuilder;
+    use super::LogsOptionsBuilder;
+    use super::RegistryAuth;
+    use super::VolumeCreateOptionsBuilder;
+    use super::VolumeFilter;
+    use super::VolumeListOptionsBuilder;
+    use communicate::version::ApiVersion;
+    use futures::Future;
+    use futures::Stream;
+    use std::io;
+    use std::io::Cursor;
+    use std::time::Duration;
+    use std::time::UNIX_EPOCH;
+    use ErrorKind;
 
     #[test]
     fn container_options_simple() {
@@ -1224,4 +2396,253 @@ mod tests {
             options.serialize().expect("Error during serialization")
         );
     }
+
+    #[test]
+    fn container_options_ports() {
+        let options = ContainerOptionsBuilder::new("test_image")
+            .expose(8080, "tcp")
+            .publish(5432, "tcp", "", None)
+            .publish(53, "udp", "127.0.0.1", Some(5353))
+            .build();
+
+        assert_eq!(
+            concat!(
+                r#"{"ExposedPorts":{"53/udp":{},"5432/tcp":{},"8080/tcp":{}},"#,
+                r#""HostConfig":{"PortBindings":{"#,
+                r#""53/udp":[{"HostIp":"127.0.0.1","HostPort":"5353"}],"#,
+                r#""5432/tcp":[{"HostIp":"","HostPort":""}]}},"#,
+                r#""Image":"test_image"}"#
+            ),
+            options.serialize().expect("Error during serialization")
+        );
+    }
+
+    #[test]
+    fn container_options_resources() {
+        let options = ContainerOptionsBuilder::new("test_image")
+            .memory(512 * 1024 * 1024)
+            .memory_swap(-1)
+            .memory_reservation(256 * 1024 * 1024)
+            .nano_cpus(1_500_000_000)
+            .cpu_shares(512)
+            .cpu_quota(50_000)
+            .cpu_period(100_000)
+            .cpuset_cpus("0-1")
+            .pids_limit(100)
+            .blkio_weight(300)
+            .ulimit("nofile", 1024, 4096)
+            .ulimit("nproc", 64, 64)
+            .shm_size(64 * 1024 * 1024)
+            .oom_kill_disable(false)
+            .cgroup_parent("/untrusted")
+            .build();
+
+        assert_eq!(
+            concat!(
+                r#"{"HostConfig":{"BlkioWeight":300,"CgroupParent":"/untrusted","#,
+                r#""CpuPeriod":100000,"CpuQuota":50000,"CpuShares":512,"#,
+                r#""CpusetCpus":"0-1","Memory":536870912,"#,
+                r#""MemoryReservation":268435456,"MemorySwap":-1,"#,
+                r#""NanoCpus":1500000000,"OomKillDisable":false,"PidsLimit":100,"#,
+                r#""ShmSize":67108864,"Ulimits":[{"Hard":4096,"Name":"nofile","Soft":1024},"#,
+                r#"{"Hard":64,"Name":"nproc","Soft":64}]},"Image":"test_image"}"#
+            ),
+            options.serialize().expect("Error during serialization")
+        );
+    }
+
+    #[test]
+    fn volume_create_options() {
+        let options = VolumeCreateOptionsBuilder::new()
+            .name("scratch")
+            .driver("local")
+            .label("purpose", "test")
+            .build();
+
+        assert_eq!(
+            r#"{"Driver":"local","Labels":{"purpose":"test"},"Name":"scratch"}"#,
+            ser_to_string(&de_to_value(&options).expect("Error during serialization"))
+                .expect("Error during serialization")
+        );
+    }
+
+    #[test]
+    fn volume_list_options_filter() {
+        let options = VolumeListOptionsBuilder::new()
+            .filter(vec![VolumeFilter::Dangling(true)])
+            .build();
+
+        assert_eq!(
+            Some("filters=%7B%22dangling%22%3A%5B%22true%22%5D%7D".to_string()),
+            options.serialize()
+        );
+    }
+
+    #[test]
+    fn exec_options_interactive() {
+        let options = ExecContainerOptionsBuilder::new()
+            .cmd(vec!["sh"])
+            .attach_stdin(true)
+            .tty(true)
+            .user("nobody")
+            .working_dir("/tmp")
+            .build();
+
+        assert!(options.tty());
+        assert_eq!(
+            r#"{"AttachStdin":true,"Cmd":["sh"],"Tty":true,"User":"nobody","WorkingDir":"/tmp"}"#,
+            ser_to_string(&de_to_value(&options).expect("Error during serialization"))
+                .expect("Error during serialization")
+        );
+    }
+
+    #[test]
+    fn logs_options_since_until() {
+        let options = LogsOptionsBuilder::new()
+            .since(UNIX_EPOCH + Duration::new(1552645230, 5))
+            .until(UNIX_EPOCH + Duration::from_secs(1552645290))
+            .details(true)
+            .build();
+        let query = options.serialize().expect("Empty query");
+
+        assert!(query.contains("since=1552645230.000000005"));
+        assert!(query.contains("until=1552645290.000000000"));
+        assert!(query.contains("details=true"));
+
+        let options = LogsOptionsBuilder::new()
+            .since_ago(Duration::from_secs(u64::MAX))
+            .build();
+        let query = options.serialize().expect("Empty query");
+        assert!(query.contains("since=0.000000000"));
+    }
+
+    fn decode(encoded: &str) -> String {
+        let bytes = base64::decode_config(encoded, base64::URL_SAFE).expect("Invalid base64");
+        String::from_utf8(bytes).expect("Invalid UTF-8")
+    }
+
+    #[test]
+    fn registry_auth() {
+        let auth = RegistryAuth::builder()
+            .username("ci")
+            .password("s3cr3t?")
+            .server_address("registry.example.com:5000")
+            .build();
+
+        assert_eq!(
+            r#"{"username":"ci","password":"s3cr3t?","serveraddress":"registry.example.com:5000"}"#,
+            decode(&auth.serialize())
+        );
+        assert_eq!(
+            r#"{"identitytoken":"abc"}"#,
+            decode(&RegistryAuth::token("abc").serialize())
+        );
+
+        let debug = format!("{:?} {:?}", auth, RegistryAuth::token("abc"));
+        assert!(debug.contains("ci"));
+        assert!(!debug.contains("s3cr3t") && !debug.contains("abc"));
+    }
+
+    #[test]
+    fn build_options_serialize() {
+        let options = BuildOptionsBuilder::new(".")
+            .tag("app")
+            .build_arg("VERSION", "1.0")
+            .label("maintainer", "ci")
+            .cache_from("app:cache")
+            .memory(1 << 30)
+            .extra_host("db", "10.0.0.2")
+            .extra_host("cache", "10.0.0.3")
+            .target("release")
+            .build();
+
+        let mut params = form_urlencoded::parse(
+            options.serialize().expect("No build options").as_bytes(),
+        );
+        params.sort();
+        assert_eq!(
+            params,
+            vec![
+                ("buildargs".to_string(), r#"{"VERSION":"1.0"}"#.to_string()),
+                ("cachefrom".to_string(), r#"["app:cache"]"#.to_string()),
+                ("extrahosts".to_string(), "cache:10.0.0.3".to_string()),
+                ("extrahosts".to_string(), "db:10.0.0.2".to_string()),
+                ("labels".to_string(), r#"{"maintainer":"ci"}"#.to_string()),
+                ("memory".to_string(), "1073741824".to_string()),
+                ("t".to_string(), "app".to_string()),
+                ("target".to_string(), "release".to_string()),
+            ]
+        );
+        assert_eq!(
+            options.required_api_version(),
+            Some((ApiVersion::new(1, 29), "BuildOptionsBuilder::target"))
+        );
+    }
+
+    #[test]
+    fn build_context_reader() {
+        let tar = (0..200_000).map(|i| i as u8).collect::<Vec<_>>();
+        let body = BuildContext::tar_reader(Cursor::new(tar.clone()))
+            .into_body(&BuildOptionsBuilder::new(".").build())
+            .expect("Body creation failed");
+
+        let read = body.concat2().wait().expect("Body read failed");
+        assert_eq!(read.as_ref(), tar.as_slice());
+    }
+
+    #[test]
+    fn build_context_missing_directory() {
+        let err = BuildContext::directory("/nonexistent/async_docker")
+            .into_body(&BuildOptionsBuilder::new(".").build())
+            .expect_err("Body creation succeeded");
+        match *err.kind() {
+            ErrorKind::Io(ref err) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn build_context_files() {
+        let context = BuildContext::files(vec![("Dockerfile", "FROM alpine\n")]);
+        let tar = context
+            .into_body(&BuildOptionsBuilder::new(".").build())
+            .expect("Body creation failed")
+            .concat2()
+            .wait()
+            .expect("Body read failed");
+        assert_eq!(&tar[..10], b"Dockerfile");
+        assert_eq!(&tar[512..524], b"FROM alpine\n");
+
+        let opts = BuildOptionsBuilder::new(".")
+            .compression(Compression::Fast)
+            .build();
+        let gzip = BuildContext::files(vec![("Dockerfile", "FROM alpine\n")])
+            .into_body(&opts)
+            .expect("Body creation failed")
+            .concat2()
+            .wait()
+            .expect("Body read failed");
+        assert_eq!(&gzip[..2], &[0x1f, 0x8b]);
+    }
+
+    #[test]
+    fn build_options_registry_config() {
+        assert!(BuildOptionsBuilder::new(".").build().registry_config().is_none());
+
+        let options = BuildOptionsBuilder::new(".")
+            .auth(RegistryAuth::token("abc"))
+            .auth(
+                RegistryAuth::builder()
+                    .username("ci")
+                    .password("pw")
+                    .server_address("registry.example.com")
+                    .build(),
+            ).build();
+        let config = options.registry_config().expect("No registry config");
+
+        assert_eq!(
+            r#"{"https://index.docker.io/v1/":{"identitytoken":"abc"},"registry.example.com":{"username":"ci","password":"pw","serveraddress":"registry.example.com"}}"#,
+            decode(&config)
+        );
+    }
 }
@@ -7,33 +7,60 @@ use Error;
 use Result;
 
 use util::build_simple_query;
+use util::PATH_STAT_HEADER;
 
-use transport::parse::parse_to_lines;
+use transport::parse::parse_to_body;
+use transport::parse::parse_to_header;
 use transport::parse::parse_to_stream;
 use transport::parse::parse_to_trait;
+use transport::parse::parse_to_upgraded;
 use transport::parse::status_code;
 
+use build::AttachOptions;
 use build::ContainerArchivePutOptions;
 use build::ExecContainerOptions;
 use build::RmContainerOptions;
-use communicate::util::AsSlice;
+use communicate::exec::Exec;
+use communicate::version::require_version;
 use errors::ErrorKind as EK;
 use futures::future;
+use futures::future::Either;
+use futures::future::Loop;
+use futures::sync::oneshot;
 use http::StatusCode;
 use hyper::Body;
 use hyper::Chunk;
 use representation::rep::Change;
+use representation::rep::ExecOutput;
 use representation::rep::Exit;
+use representation::rep::LogLine;
+use representation::rep::PathStat;
 use representation::rep::Stats;
 use representation::rep::Top;
 use serde_json::Value;
+use std::error::Error as StdError;
+use std::fs;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
+use tarball::body;
 use tarball::tarball;
+use tarball::tarball::Format;
+use tarball::unpack;
 use transport::interact::InteractApi;
 use transport::interact::InteractApiExt;
+use transport::logs;
 use transport::tty;
 
+pub use transport::tty::Output as AttachOutput;
+pub use transport::tty::Stdin as AttachStdin;
+
+/// Times an exec instance is inspected again when the daemon still reports it running
+/// after its output ended, which it does for a moment
+const EXEC_EXIT_RETRIES: u32 = 20;
+const EXEC_EXIT_RETRY_DELAY: Duration = Duration::from_millis(50);
+
 /// Interface for accessing and manipulating a docker container
 pub struct Container {
     interact: Arc<InteractApi>,
@@ -68,22 +95,62 @@ impl Container {
         parse_to_trait::<ContainerDetails>(self.interact.get(args))
     }
 
+    /// Host port `port` of the container is published on, None when it isn't
+    /// published or the container isn't running. Ephemeral ports are only assigned
+    /// once the container is started.
+    pub fn host_port(
+        &self,
+        port: u16,
+        protocol: &str,
+    ) -> impl Future<Item = Option<u16>, Error = Error> + Send {
+        let protocol = protocol.to_owned();
+        self.inspect()
+            .map(move |details| details.NetworkSettings.host_port(port, &protocol))
+    }
+
     /// Returns a `top` view of information about the container process
     pub fn top(&self, psargs: Option<&str>) -> impl Future<Item = Top, Error = Error> + Send {
         let path = format!("/containers/{}/top", self.id);
         let query = build_simple_query("ps_args", psargs);
-        let args = (path.as_ref(), query.as_slice());
+        let args = (path.as_ref(), query.as_deref());
 
         parse_to_trait::<Top>(self.interact.get(args))
     }
 
     /// Returns a stream of logs emitted but the container instance
-    pub fn logs(&self, opts: &LogsOptions) -> impl Stream<Item = String, Error = Error> + Send {
+    pub fn logs(&self, opts: &LogsOptions) -> impl Stream<Item = LogLine, Error = Error> + Send {
         let path = format!("/containers/{}/logs", self.id);
         let query = opts.serialize();
-        let args = (path.as_str(), query.as_slice());
+        let timestamps = opts.timestamps();
+        let interact = self.interact.clone();
+
+        let supported = require_version(&self.interact, opts.required_api_version());
 
-        parse_to_lines(self.interact.get(args))
+        let tty = match opts.tty {
+            Some(tty) => Either::A(future::ok(tty)),
+            None => Either::B(self.is_tty()),
+        };
+
+        future::result(supported).and_then(|_| tty).and_then(move |tty| {
+            let args = (path.as_str(), query.as_deref());
+            let body = parse_to_body(interact.get(args));
+
+            let frames = if tty {
+                Either::A(body.map(|chunk| (1, chunk)))
+            } else {
+                Either::B(tty::decode(body))
+            };
+
+            Ok(logs::lines(frames, timestamps))
+        }).flatten_stream()
+    }
+
+    /// Checks whether the container was created with a TTY
+    fn is_tty(&self) -> impl Future<Item = bool, Error = Error> + Send {
+        let path = format!("/containers/{}/json", self.id);
+
+        parse_to_trait::<Value>(self.interact.get(path.as_str()))
+            .map(|details| details["Config"]["Tty"].as_bool().unwrap_or(false))
     }
 
     /// Returns a set of changes made to the container instance
@@ -97,11 +164,7 @@ impl Container {
     pub fn export(&self) -> impl Stream<Item = Chunk, Error = Error> + Send {
         let path = format!("/containers/{}/export", self.id);
 
-        self.interact
-            .get(path.as_str())
-            .and_then(|a| a.map_err(Error::from))
-            .and_then(|a| Ok(a.into_body().map_err(Error::from)))
-            .flatten_stream()
+        parse_to_body(self.interact.get(path.as_str()))
     }
 
     /// Returns a stream of stats specific to this container instance
@@ -125,7 +188,7 @@ impl Container {
     ) -> impl Future<Item = StatusCode, Error = Error> + Send {
         let path = format!("/containers/{}/stop", self.id);
         let query = build_simple_query("t", wait.map(|w| w.as_secs().to_string()));
-        let args = (path.as_str(), query.as_slice());
+        let args = (path.as_str(), query.as_deref());
 
         status_code(self.interact.post(args))
     }
@@ -137,7 +200,7 @@ impl Container {
     ) -> impl Future<Item = StatusCode, Error = Error> + Send {
         let path = format!("/containers/{}/restart", self.id);
         let query = build_simple_query("t", wait.map(|w| w.as_secs().to_string()));
-        let args = (path.as_str(), query.as_slice());
+        let args = (path.as_str(), query.as_deref());
 
         status_code(self.interact.post(args))
     }
@@ -149,7 +212,7 @@ impl Container {
     ) -> impl Future<Item = StatusCode, Error = Error> + Send {
         let path = format!("/containers/{}/kill", self.id);
         let query = build_simple_query("signal", signal.map(|sig| sig));
-        let args = (path.as_str(), query.as_slice());
+        let args = (path.as_str(), query.as_deref());
 
         status_code(self.interact.post(args))
     }
@@ -158,7 +221,7 @@ impl Container {
     pub fn rename(&self, name: &str) -> impl Future<Item = StatusCode, Error = Error> + Send {
         let path = format!("/containers/{}/rename", self.id);
         let query = build_simple_query("name", Some(name));
-        let args = (path.as_str(), query.as_slice());
+        let args = (path.as_str(), query.as_deref());
 
         status_code(self.interact.post(args))
     }
@@ -200,7 +263,7 @@ impl Container {
     ) -> impl Future<Item = StatusCode, Error = Error> + Send {
         let path = format!("/containers/{}", self.id);
         let query = opts.serialize();
-        let args = (path.as_str(), query.as_slice());
+        let args = (path.as_str(), query.as_deref());
 
         status_code(self.interact.delete(args))
     }
@@ -211,9 +274,16 @@ impl Container {
     ) -> impl Future<Item = String, Error = Error> + Send {
         let path = format!("/containers/{}/exec", self.id);
         let body = opts.serialize().map(Body::from);
-        let args = (path.as_str(), body);
+        let interact = self.interact.clone();
 
-        parse_to_trait::<Value>(self.interact.post_json(args)).and_then(|val| {
+        // Nothing is sent to a daemon not supporting the options
+        let supported = require_version(&self.interact, opts.required_api_version());
+        let response = future::result(supported).and_then(move |_| {
+            let args = (path.as_str(), body);
+            parse_to_trait::<Value>(interact.post_json(args))
+        });
+
+        response.and_then(|val| {
             debug!("{:?}", val);
             match val {
                 Value::Object(obj) => future::result(
@@ -233,18 +303,17 @@ impl Container {
     }
 
     pub fn start_exec(&self, id: String) -> impl Stream<Item = (u32, Chunk), Error = Error> {
-        let path = format!("/exec/{}/start", id);
-        let body = Some(Body::from("{}".to_string()));
-        let args = (path.as_str(), body);
-
-        let body_future = self
-            .interact
-            .post_json(args)
-            .and_then(|response| response.map_err(Error::from))
-            .and_then(|result| Ok(result.into_body().map_err(Error::from)))
-            .flatten_stream();
+        Exec::new(self.interact.clone(), id).start(false)
+    }
 
-        tty::decode(body_future)
+    /// Starts an exec instance over a hijacked connection, returning a writable
+    /// stdin half and a stream of `(stream_type, chunk)` output frames
+    pub fn start_exec_attached(
+        &self,
+        id: String,
+        tty: bool,
+    ) -> impl Future<Item = (AttachStdin, AttachOutput), Error = Error> + Send {
+        Exec::new(self.interact.clone(), id).start_attached(tty)
     }
 
     pub fn exec(
@@ -257,33 +326,624 @@ impl Container {
             .flatten_stream()
     }
 
+    /// Runs an exec instance to completion, collecting its stdout and stderr
+    /// separately together with the exit code of the command. Fails with
+    /// `ErrorKind::ExecExitCodeUnknown` when the daemon doesn't report an exit code,
+    /// or still reports the command as running about a second after its output ended.
+    pub fn exec_output(
+        &self,
+        opts: &ExecContainerOptions,
+    ) -> impl Future<Item = ExecOutput, Error = Error> + Send {
+        let interact = self.interact.clone();
+        let tty = opts.tty();
+
+        self.create_exec(opts).and_then(move |id| {
+            let exec = Exec::new(interact, id);
+            let copy_exec = exec.clone();
+
+            exec.start(tty)
+                .fold(ExecOutput::default(), |mut output, (stream, chunk)| {
+                    match stream {
+                        2 => output.stderr.extend_from_slice(&chunk),
+                        _ => output.stdout.extend_from_slice(&chunk),
+                    };
+                    Ok::<_, Error>(output)
+                }).and_then(move |output| {
+                    exec_exit_code(copy_exec).map(move |exit_code| ExecOutput {
+                        exit_code,
+                        ..output
+                    })
+                })
+        })
+    }
+
+    /// Same as `exec_output`, but fails with `ErrorKind::ExecFailed` when the
+    /// command exits with a non-zero code
+    pub fn exec_output_checked(
+        &self,
+        opts: &ExecContainerOptions,
+    ) -> impl Future<Item = ExecOutput, Error = Error> + Send {
+        self.exec_output(opts).and_then(|output| {
+            if output.exit_code == 0 {
+                Ok(output)
+            } else {
+                let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+                Err(EK::ExecFailed(output.exit_code, stderr).into())
+            }
+        })
+    }
+
+    /// Creates and starts an exec instance over a hijacked connection, so that
+    /// the command's stdin can be written to
+    pub fn exec_attached(
+        &self,
+        opts: &ExecContainerOptions,
+    ) -> impl Future<Item = (AttachStdin, AttachOutput), Error = Error> + Send {
+        let copy_self = self.clone();
+        let tty = opts.tty();
+        self.create_exec(opts)
+            .and_then(move |id| copy_self.start_exec_attached(id, tty))
+    }
+
     pub fn archive_get(&self, pth: &str) -> impl Stream<Item = Chunk, Error = Error> {
         let path = format!("/containers/{}/archive", self.id);
         let query = build_simple_query("path", Some(pth));
-        let args = (path.as_str(), query.as_slice());
+        let args = (path.as_str(), query.as_deref());
 
-        self.interact
-            .get(args)
-            .and_then(|a| a.map_err(Error::from))
-            .and_then(|a| Ok(a.into_body().map_err(Error::from)))
-            .flatten_stream()
+        parse_to_body(self.interact.get(args))
+    }
+
+    /// Downloads the file or directory at `container_path` and unpacks it into
+    /// `local_dir` while it is received. Entries escaping `local_dir` fail the copy
+    /// with `ErrorKind::UnsafeArchiveEntry`.
+    pub fn copy_from<P>(
+        &self,
+        container_path: &str,
+        local_dir: P,
+    ) -> impl Future<Item = (), Error = Error> + Send
+    where
+        P: Into<PathBuf>,
+    {
+        unpack::unpack_stream(self.archive_get(container_path), local_dir.into())
+    }
+
+    /// Stats the file or directory at `path` without downloading it, failing with
+    /// `ErrorKind::NotFound` when it doesn't exist
+    pub fn archive_stat(&self, pth: &str) -> impl Future<Item = PathStat, Error = Error> + Send {
+        let path = format!("/containers/{}/archive", self.id);
+        let query = build_simple_query("path", Some(pth));
+        let args = (path.as_str(), query.as_deref());
+
+        parse_to_header::<PathStat>(self.interact.head(args), PATH_STAT_HEADER)
     }
 
     pub fn archive_put(
         &self,
         opts: &ContainerArchivePutOptions,
     ) -> impl Future<Item = StatusCode, Error = Error> + Send {
-        let mut bytes = vec![];
+        let local_path = opts.local_path.clone();
+        let format = Format {
+            compression: opts.compression,
+            reproducible: opts.reproducible,
+        };
+
+        // Fail before sending anything when the path can't be archived
+        match fs::metadata(&local_path) {
+            Ok(_) => {
+                let body = body::body(move |writer| tarball::dir(writer, local_path, format));
+                Either::A(self.put_archive(opts, body))
+            }
+            Err(err) => Either::B(future::err(err.into())),
+        }
+    }
+
+    /// Uploads a tar archive, optionally compressed, read from `tar` as the daemon
+    /// consumes it. The local path and format of the options are ignored.
+    pub fn archive_put_stream<S>(
+        &self,
+        opts: &ContainerArchivePutOptions,
+        tar: S,
+    ) -> impl Future<Item = StatusCode, Error = Error> + Send
+    where
+        S: Stream + Send + 'static,
+        S::Error: Into<Box<StdError + Send + Sync>>,
+        Chunk: From<S::Item>,
+    {
+        self.put_archive(opts, Body::wrap_stream(tar))
+    }
+
+    /// Writes in-memory files given as `(path, mode, content)` below `remote_dir`,
+    /// which must exist in the container. Missing parent directories of the files
+    /// are created.
+    pub fn put_files<I, P, C>(
+        &self,
+        remote_dir: &str,
+        files: I,
+    ) -> impl Future<Item = StatusCode, Error = Error> + Send
+    where
+        I: IntoIterator<Item = (P, u32, C)>,
+        P: Into<String>,
+        C: Into<Vec<u8>>,
+    {
+        let files = files
+            .into_iter()
+            .map(|(path, mode, content)| (path.into(), mode, content.into()))
+            .collect::<Vec<(String, u32, Vec<u8>)>>();
+        let body = body::body(move |writer| {
+            let files = files
+                .iter()
+                .map(|(path, mode, content)| (path.as_str(), *mode, content.as_slice()));
+            tarball::files(writer, files, Format::default())
+        });
+
+        let opts = ContainerArchivePutOptions::builder()
+            .remote_path(remote_dir.to_string())
+            .build();
+        self.put_archive(&opts, body)
+    }
+
+    fn put_archive(
+        &self,
+        opts: &ContainerArchivePutOptions,
+        body: Body,
+    ) -> impl Future<Item = StatusCode, Error = Error> + Send {
         let path = format!("/containers/{}/archive", self.id);
         let query = opts.serialize();
         let interact = self.interact.clone();
 
-        future::result(tarball::dir(&mut bytes, &opts.local_path)).and_then(move |_| {
-            let body = Some(Body::from(bytes));
-            let args = (path.as_str(), query.as_slice(), body);
+        let supported = require_version(&self.interact, opts.required_api_version());
+        future::result(supported).and_then(move |_| {
+            let args = (path.as_str(), query.as_deref(), Some(body));
             status_code(interact.put(args))
         })
     }
 
-    // todo attach, attach/ws, copy
+    /// Attaches to the container over a hijacked connection, returning a writable
+    /// stdin half and a stream of `(stream_type, chunk)` output frames
+    pub fn attach(
+        &self,
+        opts: &AttachOptions,
+    ) -> impl Future<Item = (AttachStdin, AttachOutput), Error = Error> + Send {
+        let path = format!("/containers/{}/attach", self.id);
+        let query = opts.serialize();
+        let args = (path.as_str(), query.as_deref());
+        let tty = opts.tty;
+
+        parse_to_upgraded(self.interact.post_upgrade(args))
+            .map(move |upgraded| tty::split(upgraded, tty))
+    }
+
+    // todo attach/ws, copy
+}
+
+/// Exit code of an exec instance whose output ended
+fn exec_exit_code(exec: Exec) -> impl Future<Item = i64, Error = Error> + Send {
+    future::loop_fn(0, move |attempt| {
+        exec.inspect()
+            .and_then(move |details| match details.ExitCode {
+                Some(exit_code) if !details.Running => {
+                    Either::A(future::ok(Loop::Break(exit_code)))
+                }
+                _ if attempt < EXEC_EXIT_RETRIES => Either::B(
+                    delay(EXEC_EXIT_RETRY_DELAY).map(move |_| Loop::Continue(attempt + 1)),
+                ),
+                _ => Either::A(future::err(EK::ExecExitCodeUnknown(details.ID).into())),
+            })
+    })
+}
+
+/// Resolves after `duration`, sleeping on its own thread so that no tokio timer is
+/// needed
+fn delay(duration: Duration) -> impl Future<Item = (), Error = Error> + Send {
+    let (sender, receiver) = oneshot::channel();
+    let spawned = thread::Builder::new()
+        .name("exec-delay".to_string())
+        .spawn(move || {
+            thread::sleep(duration);
+            let _ = sender.send(());
+        });
+
+    future::result(spawned)
+        .from_err()
+        .and_then(|_| receiver.map_err(|_| "Delay thread failed".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tar;
+
+    use self::tar::Archive;
+    use super::EXEC_EXIT_RETRIES;
+    use base64;
+    use communicate::new_docker_with_transport;
+    use communicate::version::ApiVersion;
+    use futures::stream;
+    use futures::Future;
+    use http::header::HeaderName;
+    use http::header::HeaderValue;
+    use hyper::Method;
+    use hyper::StatusCode;
+    use std::borrow::Cow;
+    use std::io;
+    use std::sync::Arc;
+    use transport::mock::MockDocker;
+    use ContainerArchivePutOptions;
+    use ErrorKind;
+    use ExecContainerOptions;
+
+    /// Trimmed `docker inspect` output of a daemon running API 1.43
+    const INSPECT: &str = r#"{
+        "Id": "4fa6e0f0c6786287e131c3852c58a2e01cc697a68231826813597e4994f1d6e2",
+        "Created": "2023-06-01T12:00:00.000000000Z",
+        "Path": "docker-entrypoint.sh",
+        "Args": ["postgres"],
+        "State": {
+            "Status": "running", "Running": true, "Paused": false, "Restarting": false,
+            "OOMKilled": false, "Dead": false, "Pid": 4242, "ExitCode": 0, "Error": "",
+            "StartedAt": "2023-06-01T12:00:01.000000000Z",
+            "FinishedAt": "0001-01-01T00:00:00Z",
+            "Health": {
+                "Status": "healthy",
+                "FailingStreak": 0,
+                "Log": [{
+                    "Start": "2023-06-01T12:00:31.000000000Z",
+                    "End": "2023-06-01T12:00:31.100000000Z",
+                    "ExitCode": 0,
+                    "Output": "accepting connections\n"
+                }]
+            }
+        },
+        "Image": "sha256:0c5e2ab9a9e5",
+        "ResolvConfPath": "", "HostnamePath": "", "HostsPath": "", "LogPath": "",
+        "Name": "/db", "RestartCount": 0, "Driver": "overlay2", "Platform": "linux",
+        "MountLabel": "", "ProcessLabel": "", "AppArmorProfile": "docker-default",
+        "ExecIDs": null,
+        "HostConfig": {
+            "Binds": ["/srv/db:/var/lib/postgresql/data"],
+            "ContainerIDFile": "",
+            "LogConfig": {"Type": "json-file", "Config": {}},
+            "NetworkMode": "default",
+            "PortBindings": {"5432/tcp": [{"HostIp": "", "HostPort": ""}]},
+            "RestartPolicy": {"Name": "on-failure", "MaximumRetryCount": 3},
+            "AutoRemove": false, "VolumeDriver": "", "VolumesFrom": null,
+            "ConsoleSize": [0, 0], "CapAdd": null, "CapDrop": null,
+            "CgroupnsMode": "private", "Dns": [], "DnsOptions": [], "DnsSearch": [],
+            "ExtraHosts": null, "GroupAdd": null, "IpcMode": "private", "Cgroup": "",
+            "Links": null, "OomScoreAdj": 0, "PidMode": "", "Privileged": false,
+            "PublishAllPorts": false, "ReadonlyRootfs": false,
+            "SecurityOpt": ["no-new-privileges"], "UTSMode": "", "UsernsMode": "",
+            "ShmSize": 67108864, "Runtime": "runc", "Isolation": "",
+            "CpuShares": 0, "Memory": 536870912, "NanoCpus": 1500000000,
+            "CgroupParent": "", "BlkioWeight": 0, "BlkioWeightDevice": [],
+            "BlkioDeviceReadBps": [], "BlkioDeviceWriteBps": [],
+            "BlkioDeviceReadIOps": [], "BlkioDeviceWriteIOps": [],
+            "CpuPeriod": 0, "CpuQuota": 0, "CpuRealtimePeriod": 0,
+            "CpuRealtimeRuntime": 0, "CpusetCpus": "", "CpusetMems": "",
+            "Devices": [], "DeviceCgroupRules": null, "DeviceRequests": null,
+            "MemoryReservation": 0, "MemorySwap": -1, "MemorySwappiness": null,
+            "OomKillDisable": null, "PidsLimit": null,
+            "Ulimits": [{"Name": "nofile", "Soft": 1024, "Hard": 4096}],
+            "CpuCount": 0, "CpuPercent": 0, "IOMaximumIOps": 0, "IOMaximumBandwidth": 0,
+            "MaskedPaths": ["/proc/kcore"], "ReadonlyPaths": ["/proc/sys"]
+        },
+        "Mounts": [{
+            "Type": "bind", "Source": "/srv/db",
+            "Destination": "/var/lib/postgresql/data",
+            "Mode": "", "RW": true, "Propagation": "rprivate"
+        }],
+        "Config": {
+            "Hostname": "4fa6e0f0c678", "Domainname": "", "User": "",
+            "AttachStdin": false, "AttachStdout": false, "AttachStderr": false,
+            "ExposedPorts": {"5432/tcp": {}},
+            "Tty": false, "OpenStdin": false, "StdinOnce": false,
+            "Env": ["POSTGRES_PASSWORD=secret"], "Cmd": ["postgres"],
+            "Image": "postgres:15", "Volumes": {"/var/lib/postgresql/data": {}},
+            "WorkingDir": "", "Entrypoint": ["docker-entrypoint.sh"], "OnBuild": null,
+            "Labels": {}, "StopSignal": "SIGINT"
+        },
+        "NetworkSettings": {
+            "Bridge": "", "SandboxID": "1d4bd2e9ef4d", "HairpinMode": false,
+            "LinkLocalIPv6Address": "", "LinkLocalIPv6PrefixLen": 0,
+            "Ports": {
+                "5432/tcp": [
+                    {"HostIp": "0.0.0.0", "HostPort": "49153"},
+                    {"HostIp": "::", "HostPort": "49153"}
+                ],
+                "8080/tcp": null
+            },
+            "SandboxKey": "/var/run/docker/netns/1d4bd2e9ef4d",
+            "SecondaryIPAddresses": null, "SecondaryIPv6Addresses": null,
+            "EndpointID": "b2a7", "Gateway": "172.17.0.1", "GlobalIPv6Address": "",
+            "GlobalIPv6PrefixLen": 0, "IPAddress": "172.17.0.2", "IPPrefixLen": 16,
+            "IPv6Gateway": "", "MacAddress": "02:42:ac:11:00:02",
+            "Networks": {
+                "bridge": {
+                    "IPAMConfig": null, "Links": null, "Aliases": null,
+                    "NetworkID": "8f3c", "EndpointID": "b2a7", "Gateway": "172.17.0.1",
+                    "IPAddress": "172.17.0.2", "IPPrefixLen": 16, "IPv6Gateway": "",
+                    "GlobalIPv6Address": "", "GlobalIPv6PrefixLen": 0,
+                    "MacAddress": "02:42:ac:11:00:02", "DriverOpts": null
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn inspect_details() {
+        let mut mock = MockDocker::new();
+        mock.expect(Method::GET, "/containers/db/json").body(INSPECT);
+
+        let docker = new_docker_with_transport(Arc::new(mock));
+        let details = docker
+            .container(Cow::from("db"))
+            .inspect()
+            .wait()
+            .expect("Inspect failed");
+
+        assert_eq!(details.Name, "/db");
+        assert_eq!(details.Platform.as_deref(), Some("linux"));
+        let health = details.State.Health.expect("Health missing");
+        assert_eq!(health.Status, "healthy");
+        assert_eq!(health.Log.expect("Health log missing")[0].ExitCode, 0);
+
+        let host_config = details.HostConfig;
+        assert_eq!(host_config.Memory, 512 * 1024 * 1024);
+        assert_eq!(host_config.MemorySwap, -1);
+        assert_eq!(host_config.PidsLimit, None);
+        let restart = host_config.RestartPolicy.expect("Restart policy missing");
+        assert_eq!((restart.Name.as_str(), restart.MaximumRetryCount), ("on-failure", 3));
+        assert_eq!(host_config.Ulimits.expect("Ulimits missing")[0].Hard, 4096);
+
+        let ports = details.NetworkSettings.Ports.expect("Ports missing");
+        let bindings = ports["5432/tcp"].as_ref().expect("Port not published");
+        assert_eq!(bindings[0].HostPort, "49153");
+        assert!(ports["8080/tcp"].is_none());
+        let networks = details.NetworkSettings.Networks.expect("Networks missing");
+        assert_eq!(networks["bridge"].IPAddress, "172.17.0.2");
+    }
+
+    #[test]
+    fn inspect_details_of_older_daemons() {
+        let inspect = r#"{
+            "Id": "4fa6e0f0c678", "Name": "/db",
+            "State": {"Running": false, "ExitCode": 0},
+            "Config": {"Image": "postgres", "Labels": null},
+            "Mounts": [{"Source": "/data", "Destination": "/var/lib/postgresql", "RW": true}]
+        }"#;
+
+        let mut mock = MockDocker::new();
+        mock.expect(Method::GET, "/containers/db/json").body(inspect);
+
+        let docker = new_docker_with_transport(Arc::new(mock));
+        let details = docker
+            .container(Cow::from("db"))
+            .inspect()
+            .wait()
+            .expect("Inspect failed");
+
+        assert_eq!(details.State.Error, "");
+        assert!(details.Config.Labels.is_none());
+        assert_eq!(details.Mounts[0].Mode, "");
+        assert!(details.Mounts[0].RW);
+    }
+
+    #[test]
+    fn exec_output_unknown_exit_code() {
+        let details = |running: bool, exit_code: &str| {
+            format!(
+                concat!(
+                    r#"{{"ID":"e1","ContainerID":"web","Running":{},"ExitCode":{},"#,
+                    r#""CanRemove":false,"DetachKeys":"","OpenStdin":false,"#,
+                    r#""OpenStdout":true,"OpenStderr":true,"Pid":42,"#,
+                    r#""ProcessConfig":{{"tty":false,"entrypoint":"true","arguments":[]}}}}"#
+                ),
+                running, exit_code
+            )
+        };
+
+        let mut mock = MockDocker::new();
+        mock.expect(Method::POST, "/containers/web/exec")
+            .body(r#"{"Id":"e1"}"#)
+            .times(3);
+        mock.expect(Method::POST, "/exec/e1/start").times(3);
+        mock.expect(Method::GET, "/exec/e1/json")
+            .body(details(false, "null"))
+            .times(EXEC_EXIT_RETRIES as usize + 1);
+        mock.expect(Method::GET, "/exec/e1/json")
+            .body(details(true, "0"))
+            .times(EXEC_EXIT_RETRIES as usize + 1);
+        mock.expect(Method::GET, "/exec/e1/json")
+            .body(details(true, "null"))
+            .times(2);
+        mock.expect(Method::GET, "/exec/e1/json")
+            .body(details(false, "3"));
+
+        let mock = Arc::new(mock);
+        let docker = new_docker_with_transport(mock.clone());
+        let container = docker.container(Cow::from("web"));
+        let opts = ExecContainerOptions::builder()
+            .cmd(vec!["true"])
+            .attach_stdout(true)
+            .build();
+
+        for _ in 0..2 {
+            match *container
+                .exec_output_checked(&opts)
+                .wait()
+                .expect_err("Exec succeeded")
+                .kind()
+            {
+                ErrorKind::ExecExitCodeUnknown(ref id) => assert_eq!(id, "e1"),
+                ref kind => panic!("Unexpected error: {:?}", kind),
+            }
+        }
+
+        // Still running for a moment after the output ended
+        let output = container.exec_output(&opts).wait().expect("Exec failed");
+        assert_eq!(output.exit_code, 3);
+        mock.verify();
+    }
+
+    #[test]
+    fn archive_put_files_and_streams() {
+        let mut mock = MockDocker::new();
+        mock.expect(Method::PUT, "/containers/web/archive")
+            .query("path=%2Fetc%2Fapp");
+        mock.expect(Method::PUT, "/containers/web/archive")
+            .query("copyUIDGID=true&noOverwriteDirNonDir=true&path=%2Fsrv");
+
+        let mock = Arc::new(mock);
+        let docker = new_docker_with_transport(mock.clone());
+        let container = docker.container(Cow::from("web"));
+
+        let files = vec![
+            ("app.toml", 0o644, "port = 8080\n"),
+            ("bin/start.sh", 0o755, "#!/bin/sh\n"),
+        ];
+        let status = container
+            .put_files("/etc/app", files)
+            .wait()
+            .expect("Put failed");
+        assert_eq!(status, StatusCode::OK);
+
+        let body = mock.bodies().remove(0);
+        let mut archive = Archive::new(body.as_slice());
+        let headers = archive
+            .entries_mut()
+            .expect("Invalid archive")
+            .map(|entry| {
+                let header = entry.expect("Invalid entry").header().clone();
+                let path = header.path().expect("Invalid path").display().to_string();
+                assert!(header.mtime().expect("Invalid mtime") > 0);
+                (path, header.mode().expect("Invalid mode"))
+            }).collect::<Vec<_>>();
+        assert_eq!(
+            headers,
+            vec![
+                ("app.toml".to_string(), 0o644),
+                ("bin/start.sh".to_string(), 0o755),
+            ]
+        );
+
+        let opts = ContainerArchivePutOptions::builder()
+            .remote_path("/srv".to_string())
+            .no_overwrite(true)
+            .copy_uid_gid(true)
+            .build();
+        let tar = stream::once::<_, io::Error>(Ok(vec![0u8; 1024]));
+        container
+            .archive_put_stream(&opts, tar)
+            .wait()
+            .expect("Put failed");
+        assert_eq!(mock.bodies()[1], vec![0u8; 1024]);
+
+        mock.verify();
+    }
+
+    #[test]
+    fn archive_put_missing_path() {
+        // Nothing is expected, any request would fail the test
+        let mock = Arc::new(MockDocker::new());
+        let docker = new_docker_with_transport(mock.clone());
+
+        let opts = ContainerArchivePutOptions::builder()
+            .remote_path("/srv".to_string())
+            .local_path("/nonexistent/async_docker".to_string())
+            .build();
+        let err = docker
+            .container(Cow::from("web"))
+            .archive_put(&opts)
+            .wait()
+            .expect_err("Put succeeded");
+        match *err.kind() {
+            ErrorKind::Io(ref err) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+        assert!(mock.bodies().is_empty());
+    }
+
+    #[test]
+    fn archive_stat() {
+        let stat = concat!(
+            r#"{"name":"app.log","size":8,"mode":420,"#,
+            r#""mtime":"2018-10-01T12:00:00Z","linkTarget":""}"#
+        );
+
+        let mut mock = MockDocker::new();
+        mock.expect(Method::HEAD, "/containers/web/archive")
+            .query("path=%2Fvar%2Flog%2Fapp.log")
+            .header(
+                HeaderName::from_static("x-docker-container-path-stat"),
+                HeaderValue::from_str(&base64::encode(stat)).expect("Invalid header"),
+            );
+        mock.expect(Method::HEAD, "/containers/web/archive")
+            .query("path=%2Fmissing")
+            .status(StatusCode::NOT_FOUND);
+
+        let docker = new_docker_with_transport(Arc::new(mock));
+        let container = docker.container(Cow::from("web"));
+
+        let stat = container
+            .archive_stat("/var/log/app.log")
+            .wait()
+            .expect("Stat failed");
+        assert_eq!((stat.name.as_str(), stat.size), ("app.log", 8));
+        assert_eq!(stat.permissions(), 0o644);
+        assert!(!stat.is_dir());
+
+        match *container
+            .archive_stat("/missing")
+            .wait()
+            .expect_err("Stat succeeded")
+            .kind()
+        {
+            ErrorKind::NotFound(..) => {}
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn options_require_api_version() {
+        let mut mock = MockDocker::new();
+        mock.set_api_version(ApiVersion::new(1, 34));
+
+        let docker = new_docker_with_transport(Arc::new(mock));
+        let opts = ExecContainerOptions::builder()
+            .cmd(vec!["ls"])
+            .working_dir("/srv")
+            .build();
+
+        match *docker
+            .container(Cow::from("web"))
+            .create_exec(&opts)
+            .wait()
+            .expect_err("Exec created")
+            .kind()
+        {
+            ErrorKind::UnsupportedApiVersion(feature, ..) => {
+                assert_eq!(feature, "ExecContainerOptions::working_dir")
+            }
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+
+        let opts = ContainerArchivePutOptions::builder()
+            .remote_path("/srv".to_string())
+            .copy_uid_gid(true)
+            .build();
+        let tar = stream::once::<_, io::Error>(Ok(vec![0u8; 1024]));
+
+        match *docker
+            .container(Cow::from("web"))
+            .archive_put_stream(&opts, tar)
+            .wait()
+            .expect_err("Put succeeded")
+            .kind()
+        {
+            ErrorKind::UnsupportedApiVersion(feature, required, _) => {
+                assert_eq!(feature, "ContainerArchiveOptionsBuilder::copy_uid_gid");
+                assert_eq!(required, ApiVersion::new(1, 40));
+            }
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+    }
 }
@@ -1,7 +1,6 @@
 use build::ContainerListOptions;
 use build::ContainerOptions;
 use communicate::util::build_simple_query;
-use communicate::util::AsSlice;
 use futures::Future;
 use hyper::Body;
 use rep::Container as ContainerRep;
@@ -30,7 +29,7 @@ impl Containers {
     ) -> impl Future<Item = Vec<ContainerRep>, Error = Error> {
         let path = "/containers/json";
         let query = opts.serialize();
-        let args = (path, query.as_slice());
+        let args = (path, query.as_deref());
 
         parse_to_trait::<Vec<ContainerRep>>(self.interact.get(args))
     }
@@ -46,7 +45,7 @@ impl Containers {
             .serialize()
             .expect("Error during serialization of ContainerOptions");
         let body = Some(Body::from(data));
-        let args = (path, query.as_slice(), body);
+        let args = (path, query.as_deref(), body);
 
         parse_to_trait(self.interact.post_json(args))
     }
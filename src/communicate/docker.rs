@@ -1,10 +1,10 @@
+use futures::future;
 use futures::Future;
 use hyper::client::connect::Connect;
 use hyper::rt::Stream;
 
 use errors::{Error, ErrorKind, Result};
 
-use communicate::util::AsSlice;
 use transport::parse_to_stream;
 use transport::parse_to_trait;
 use transport::status_code;
@@ -17,25 +17,41 @@ use representation::rep::{Event, Info, Version};
 
 #[cfg(feature = "ssl")]
 use super::ssl_tcp_docker::TcpSSLDocker;
+#[cfg(feature = "rustls")]
+use super::rustls_tcp_docker::TcpRustlsDocker;
 use super::tcp_docker::TcpDocker;
 #[cfg(target_os = "linux")]
 use super::unix_docker::UnixDocker;
 use communicate::containers::Containers;
+use communicate::exec::Exec;
 use communicate::image::Image;
 use communicate::networks::Networks;
 use communicate::Container;
 use communicate::Images;
 use communicate::Network;
+use communicate::Volume;
+use communicate::Volumes;
+use http::uri::Scheme;
+use hyper::client::HttpConnector;
 use hyper::StatusCode;
 use hyper::Uri;
+use communicate::tls::TlsConfig;
+use communicate::version::ApiVersion;
+use communicate::version::CLIENT_API_VERSION;
 use std::borrow::Cow;
+use std::cmp;
 use std::marker::PhantomData;
+use std::str::FromStr;
 use std::sync::Arc;
 use transport::interact::InteractApi;
 use transport::interact::InteractApiExt;
 
 /// Entry point interface for communicating with docker daemon
-pub trait DockerApi {
+pub trait DockerApi: Send + Sync {
+    /// Returns the API version requests are pinned to. `None` means the
+    /// daemon serves each request with its own latest version
+    fn api_version(&self) -> Option<ApiVersion>;
+
     /// Returns version information associated with the docker daemon
     fn version(&self) -> Box<Future<Item = Version, Error = Error> + Send>;
 
@@ -57,6 +73,9 @@ pub trait DockerApi {
     /// Exports an interface for interacting with docker containers
     fn containers(&self) -> Containers;
 
+    /// Exports an interface for interacting with docker exec instance
+    fn exec(&self, id: Cow<'static, str>) -> Exec;
+
     /// Exports an interface for interacting with docker image
     fn image<'a>(&self, id: Cow<'a, str>) -> Image<'a>;
 
@@ -68,6 +87,12 @@ pub trait DockerApi {
 
     /// Exports an interface for interacting with networks
     fn networks(&self) -> Networks;
+
+    /// Exports an interface for interacting with volume
+    fn volume<'a>(&self, name: Cow<'a, str>) -> Volume<'a>;
+
+    /// Exports an interface for interacting with volumes
+    fn volumes(&self) -> Volumes;
 }
 
 pub(crate) struct Docker<C>
@@ -94,6 +119,10 @@ impl<C> DockerApi for Docker<C>
 where
     C: Connect + 'static,
 {
+    fn api_version(&self) -> Option<ApiVersion> {
+        self.interact.api_version()
+    }
+
     fn version(&self) -> Box<Future<Item = Version, Error = Error> + Send> {
         let arg = "/version";
 
@@ -117,7 +146,7 @@ where
         opts: &EventsOptions,
     ) -> Box<Stream<Item = Result<Event>, Error = Error> + Send> {
         let query = opts.serialize();
-        let arg = ("/events", query.as_slice());
+        let arg = ("/events", query.as_deref());
 
         Box::new(parse_to_stream::<Event>(self.interact.get(arg)))
     }
@@ -132,6 +161,11 @@ where
         Containers::new(interact)
     }
 
+    fn exec(&self, id: Cow<'static, str>) -> Exec {
+        let interact = self.interact.clone();
+        Exec::new(interact, id)
+    }
+
     fn image<'a>(&self, id: Cow<'a, str>) -> Image<'a> {
         let interact = self.interact.clone();
         Image::new(interact, id)
@@ -151,32 +185,220 @@ where
         let interact = self.interact.clone();
         Networks::new(interact)
     }
+
+    fn volume<'a>(&self, name: Cow<'a, str>) -> Volume<'a> {
+        let interact = self.interact.clone();
+        Volume::new(interact, name)
+    }
+
+    fn volumes(&self) -> Volumes {
+        let interact = self.interact.clone();
+        Volumes::new(interact)
+    }
 }
 
 fn default_uri(uri: Option<Uri>) -> Result<Uri> {
-    use communicate::util::{DEFAULT_URI, URI_ENV};
+    use communicate::util::{DEFAULT_URI, HOST_ENV, URI_ENV};
     match uri {
-        None => match env::var(URI_ENV) {
-            Ok(var) => var.parse().map_err(Error::from),
-            Err(_) => DEFAULT_URI.parse().map_err(Error::from),
+        None => match env::var(HOST_ENV).or_else(|_| env::var(URI_ENV)) {
+            Ok(ref var) if !var.is_empty() => parse_host(var),
+            _ => parse_host(DEFAULT_URI),
         },
         Some(x) => Ok(x),
     }
 }
 
-/// Creates the docker struct relevant to the provided Uri
+/// Parses a docker host in the format accepted by the docker CLI's `DOCKER_HOST`,
+/// e.g. `unix:///var/run/docker.sock` or `tcp://127.0.0.1:2376`
+pub fn parse_host(host: &str) -> Result<Uri> {
+    let host = host.trim();
+    if host.starts_with("unix:///") {
+        // An empty authority isn't a valid Uri, so the socket path is kept
+        // under the `localhost` authority instead
+        format!("unix://localhost{}", &host["unix://".len()..])
+            .parse()
+            .map_err(Error::from)
+    } else {
+        host.parse().map_err(Error::from)
+    }
+}
+
+/// Maps the `tcp` scheme used by the docker CLI to `https` when TLS is requested,
+/// or to plain `http` otherwise
+fn resolve_tcp_scheme(host: Uri, tls: bool) -> Result<Uri> {
+    if host.scheme_part().map(|s| s.as_str()) != Some("tcp") {
+        return Ok(host);
+    }
+
+    let mut parts = host.into_parts();
+    let scheme = if tls { "https" } else { "http" };
+    parts.scheme = Some(Scheme::from_str(scheme).expect("Constant scheme parsing error"));
+
+    Uri::from_parts(parts).map_err(Error::from)
+}
+
+fn tls_verify() -> bool {
+    use communicate::util::TLS_VERIFY_ENV;
+    env::var(TLS_VERIFY_ENV)
+        .map(|v| !v.is_empty())
+        .unwrap_or(false)
+}
+
+fn env_api_version() -> Result<Option<ApiVersion>> {
+    use communicate::util::API_VERSION_ENV;
+    match env::var(API_VERSION_ENV) {
+        Ok(ref var) if !var.is_empty() => var.parse().map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Creates the docker struct relevant to the provided Uri. When `host` is `None`,
+/// `DOCKER_HOST`, `DOCKER_TLS_VERIFY` and `DOCKER_API_VERSION` are honoured like the docker CLI
 pub fn new_docker(host: Option<Uri>) -> Result<Box<DockerApi>> {
-    let host = default_uri(host)?;
-    let scheme = host.scheme_part().map(|a| a.as_str().to_string());
-    match scheme.as_slice() {
-        Some(scheme) => match scheme {
-            #[cfg(target_os = "linux")]
-            "unix" => UnixDocker::new(host),
-            #[cfg(feature = "ssl")]
-            "https" => TcpSSLDocker::new(host),
-            "http" => TcpDocker::new(host),
-            _ => Err(ErrorKind::InvalidScheme.into()),
-        },
-        None => Err(ErrorKind::EmptyScheme.into()),
+    let mut builder = DockerBuilder::new();
+    if let Some(host) = host {
+        builder.host(host);
+    }
+
+    builder.build()
+}
+
+/// Creates the docker struct sending its requests through the given transport,
+/// e.g. `MockDocker` in tests
+pub fn new_docker_with_transport(transport: Arc<InteractApi>) -> Box<DockerApi> {
+    // The connector type is unused, requests go through the transport only
+    Box::new(Docker::<HttpConnector>::new_inner(transport))
+}
+
+/// Builder interface for the docker struct
+#[derive(Clone, Default)]
+pub struct DockerBuilder {
+    host: Option<Uri>,
+    version: Option<ApiVersion>,
+    tls: Option<TlsConfig>,
+}
+
+impl DockerBuilder {
+    pub fn new() -> DockerBuilder {
+        DockerBuilder {
+            ..Default::default()
+        }
+    }
+
+    /// Uri of the docker daemon. When not set, `DOCKER_HOST` is used first, then
+    /// `SHIPLIFT_URI` and finally the default unix socket
+    pub fn host(&mut self, host: Uri) -> &mut DockerBuilder {
+        self.host = Some(host);
+        self
+    }
+
+    /// Pins every request to the given API version, overriding `DOCKER_API_VERSION`.
+    /// When negotiating, it is the highest version that will be picked
+    pub fn api_version(&mut self, version: ApiVersion) -> &mut DockerBuilder {
+        self.version = Some(version);
+        self
+    }
+
+    /// TLS material for `https` hosts. When not set, it is read from `DOCKER_CERT_PATH`
+    pub fn tls(&mut self, tls: TlsConfig) -> &mut DockerBuilder {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Creates the docker struct relevant to the configured Uri
+    pub fn build(&self) -> Result<Box<DockerApi>> {
+        let version = match self.version {
+            Some(version) => Some(version),
+            None => env_api_version()?,
+        };
+
+        self.build_with_version(version)
+    }
+
+    fn build_with_version(&self, version: Option<ApiVersion>) -> Result<Box<DockerApi>> {
+        self.transport_with_version(version).map(new_docker_with_transport)
+    }
+
+    /// Creates the transport relevant to the configured Uri, to be wrapped, e.g. by
+    /// a `RecordingTransport`, before `new_docker_with_transport`
+    pub fn transport(&self) -> Result<Arc<InteractApi>> {
+        let version = match self.version {
+            Some(version) => Some(version),
+            None => env_api_version()?,
+        };
+
+        self.transport_with_version(version)
+    }
+
+    fn transport_with_version(&self, version: Option<ApiVersion>) -> Result<Arc<InteractApi>> {
+        let host = resolve_tcp_scheme(default_uri(self.host.clone())?, tls_verify())?;
+        let scheme = host.scheme_part().map(|a| a.as_str().to_string());
+
+        match scheme.as_deref() {
+            Some(scheme) => match scheme {
+                #[cfg(target_os = "linux")]
+                "unix" => UnixDocker::transport(host, version),
+                #[cfg(feature = "ssl")]
+                "https" => TcpSSLDocker::transport(host, version, self.tls.clone()),
+                #[cfg(all(feature = "rustls", not(feature = "ssl")))]
+                "https" => TcpRustlsDocker::transport(host, version, self.tls.clone()),
+                #[cfg(not(any(feature = "ssl", feature = "rustls")))]
+                "https" => Err(ErrorKind::TlsNotEnabled.into()),
+                "http" => TcpDocker::transport(host, version),
+                _ => Err(ErrorKind::InvalidScheme.into()),
+            },
+            None => Err(ErrorKind::EmptyScheme.into()),
+        }
+    }
+
+    /// Creates the docker struct pinned to the lower of the daemon's API version
+    /// and the client's, which is either the pinned version or `CLIENT_API_VERSION`
+    pub fn negotiate(&self) -> impl Future<Item = Box<DockerApi>, Error = Error> + Send {
+        let builder = self.clone();
+        let client_version = match self.version {
+            Some(version) => Ok(version),
+            None => env_api_version().map(|v| v.unwrap_or(CLIENT_API_VERSION)),
+        };
+
+        future::result(client_version)
+            .and_then(|client_version| {
+                builder
+                    .build_with_version(None)
+                    .map(|docker| (builder, client_version, docker))
+            })
+            .and_then(|(builder, client_version, docker)| {
+                docker
+                    .version()
+                    .map(move |version| (builder, client_version, version))
+            })
+            .and_then(|(builder, client_version, version)| {
+                let server_version = version.ApiVersion.parse::<ApiVersion>()?;
+                builder.build_with_version(Some(cmp::min(client_version, server_version)))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_host;
+    use super::resolve_tcp_scheme;
+
+    #[test]
+    fn parse_unix_host() {
+        let host = parse_host("unix:///var/run/docker.sock").expect("Parse failed");
+
+        assert_eq!(host.scheme_part().map(|s| s.as_str()), Some("unix"));
+        assert_eq!(host.path(), "/var/run/docker.sock");
+    }
+
+    #[test]
+    fn resolve_tcp_host() {
+        let host = parse_host("tcp://127.0.0.1:2376").expect("Parse failed");
+
+        let plain = resolve_tcp_scheme(host.clone(), false).expect("Resolve failed");
+        assert_eq!(plain.to_string(), "http://127.0.0.1:2376/");
+
+        let tls = resolve_tcp_scheme(host, true).expect("Resolve failed");
+        assert_eq!(tls.to_string(), "https://127.0.0.1:2376/");
     }
 }
@@ -0,0 +1,108 @@
+use futures::future::Either;
+use futures::Future;
+use futures::Stream;
+use http::StatusCode;
+use hyper::Body;
+use hyper::Chunk;
+use representation::rep::ExecDetails;
+use std::borrow::Cow;
+use std::sync::Arc;
+use transport::interact::InteractApi;
+use transport::interact::InteractApiExt;
+use transport::parse::parse_to_body;
+use transport::parse::parse_to_trait;
+use transport::parse::parse_to_upgraded;
+use transport::parse::status_code;
+use transport::tty;
+use transport::tty::Output;
+use transport::tty::Stdin;
+use url::form_urlencoded;
+use Error;
+
+/// Interface for accessing and manipulating a docker exec instance
+pub struct Exec {
+    interact: Arc<InteractApi>,
+    id: Cow<'static, str>,
+}
+
+impl Clone for Exec {
+    fn clone(&self) -> Exec {
+        Exec::new(self.interact.clone(), self.id.clone())
+    }
+}
+
+impl Exec {
+    /// Exports an interface exposing operations against an exec instance
+    pub(crate) fn new<S>(interact: Arc<InteractApi>, id: S) -> Exec
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        Exec {
+            interact,
+            id: id.into(),
+        }
+    }
+
+    /// a getter for the exec instance id
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Starts the exec instance and returns a stream of `(stream_type, chunk)` frames
+    /// of its output. `tty` has to match the `Tty` setting the exec instance was
+    /// created with.
+    pub fn start(&self, tty: bool) -> impl Stream<Item = (u32, Chunk), Error = Error> {
+        let path = format!("/exec/{}/start", self.id);
+        let body = Some(Body::from(start_body(tty)));
+        let args = (path.as_str(), body);
+        let body = parse_to_body(self.interact.post_json(args));
+
+        if tty {
+            Either::A(body.map(|chunk| (1, chunk)))
+        } else {
+            Either::B(tty::decode(body))
+        }
+    }
+
+    /// Starts the exec instance over a hijacked connection, returning a writable
+    /// stdin half and a stream of `(stream_type, chunk)` output frames.
+    /// `tty` has to match the `Tty` setting the exec instance was created with.
+    pub fn start_attached(
+        &self,
+        tty: bool,
+    ) -> impl Future<Item = (Stdin, Output), Error = Error> + Send {
+        let path = format!("/exec/{}/start", self.id);
+        let body = Some(Body::from(start_body(tty)));
+        let args = (path.as_str(), body);
+
+        parse_to_upgraded(self.interact.post_upgrade(args))
+            .map(move |upgraded| tty::split(upgraded, tty))
+    }
+
+    /// Resizes the TTY session of the exec instance
+    pub fn resize(
+        &self,
+        height: u64,
+        width: u64,
+    ) -> impl Future<Item = StatusCode, Error = Error> + Send {
+        let path = format!("/exec/{}/resize", self.id);
+        let query = Some(form_urlencoded::serialize(vec![
+            ("h", height.to_string()),
+            ("w", width.to_string()),
+        ]));
+        let args = (path.as_str(), query.as_deref());
+
+        status_code(self.interact.post(args))
+    }
+
+    /// Inspects the exec instance's details
+    pub fn inspect(&self) -> impl Future<Item = ExecDetails, Error = Error> + Send {
+        let path = format!("/exec/{}/json", self.id);
+
+        parse_to_trait::<ExecDetails>(self.interact.get(path.as_str()))
+    }
+}
+
+fn start_body(tty: bool) -> String {
+    format!(r#"{{"Detach":false,"Tty":{}}}"#, tty)
+}
@@ -1,3 +1,7 @@
+use build::PushOptions;
+use communicate::util::IntoRequestArgs;
+use communicate::util::ANONYMOUS_REGISTRY_AUTH;
+use communicate::util::REGISTRY_AUTH_HEADER;
 use errors::ErrorKind as EK;
 use futures::future;
 use futures::Future;
@@ -5,12 +9,15 @@ use futures::Stream;
 use hyper::Chunk;
 use representation::rep::History;
 use representation::rep::ImageDetails;
+use representation::rep::PullProgress;
 use representation::rep::Status;
 use serde_json::Value;
 use std::borrow::Cow;
 use std::sync::Arc;
 use transport::interact::InteractApi;
 use transport::interact::InteractApiExt;
+use transport::parse::parse_to_body;
+use transport::parse::parse_to_checked_stream;
 use transport::parse::parse_to_trait;
 use Error;
 use Result;
@@ -83,14 +90,30 @@ impl<'b> Image<'b> {
         })
     }
 
+    /// Pushes the image to the registry its name points to, streaming the progress
+    /// reported by the daemon. Errors reported in the middle of the push end the stream
+    /// with `ErrorKind::StreamError`.
+    pub fn push(
+        &self,
+        opts: &PushOptions,
+    ) -> impl Stream<Item = PullProgress, Error = Error> + Send {
+        let path = format!("/images/{}/push", self.name);
+        let query = opts.serialize();
+        let auth = opts
+            .auth()
+            .map(|auth| auth.serialize())
+            .unwrap_or_else(|| ANONYMOUS_REGISTRY_AUTH.to_string());
+
+        let mut args = (path.as_str(), query.as_deref()).into_request_args();
+        args.set_registry_header(REGISTRY_AUTH_HEADER, &auth);
+
+        parse_to_checked_stream::<PullProgress>(self.interact.post(args))
+    }
+
     /// Export this image to a tarball
     pub fn export(&self) -> impl Stream<Item = Chunk, Error = Error> + Send {
         let path = format!("/images/{}/get", self.name);
 
-        self.interact
-            .get(path.as_str())
-            .and_then(|a| a.map_err(Error::from))
-            .and_then(|a| Ok(a.into_body().map_err(Error::from)))
-            .flatten_stream()
+        parse_to_body(self.interact.get(path.as_str()))
     }
 }
@@ -1,20 +1,25 @@
+use build::BuildContext;
 use build::BuildOptions;
 use build::ImageListOptions;
 use build::PullOptions;
 use communicate::util::build_simple_query;
-use communicate::util::AsSlice;
+use communicate::util::IntoRequestArgs;
+use communicate::util::REGISTRY_AUTH_HEADER;
+use communicate::util::REGISTRY_CONFIG_HEADER;
+use communicate::version::require_version;
 use futures::future;
 use futures::Future;
 use futures::Stream;
-use hyper::Body;
+use http::header::HeaderValue;
+use http::header::CONTENT_TYPE;
 use rep::Image as ImageRep;
+use representation::rep::BuildOutput;
+use representation::rep::PullProgress;
 use representation::rep::SearchResult;
-use representation::rep::Top;
-use serde_json::Value;
 use std::sync::Arc;
-use tarball::tarball;
 use transport::interact::InteractApi;
 use transport::interact::InteractApiExt;
+use transport::parse::parse_to_checked_stream;
 use transport::parse::parse_to_lines;
 use transport::parse::parse_to_trait;
 use url::form_urlencoded;
@@ -31,20 +36,42 @@ impl Images {
         Images { interact }
     }
 
-    /// Builds a new image build by reading a Dockerfile in a target directory
-    pub fn build(&self, opts: &BuildOptions) -> impl Future<Item = Vec<Top>, Error = Error> + Send {
-        let mut bytes = vec![];
+    /// Builds a new image by reading a Dockerfile in the directory of the options,
+    /// streaming the output of the build. Errors reported in the middle of the build
+    /// end the stream with `ErrorKind::StreamError`.
+    pub fn build(
+        &self,
+        opts: &BuildOptions,
+    ) -> impl Stream<Item = BuildOutput, Error = Error> + Send {
+        self.build_with_context(opts, BuildContext::directory(opts.path.as_str()))
+    }
+
+    /// Builds a new image from the given context, ignoring the path of the options
+    pub fn build_with_context(
+        &self,
+        opts: &BuildOptions,
+        context: BuildContext,
+    ) -> impl Stream<Item = BuildOutput, Error = Error> + Send {
         let interact = self.interact.clone();
 
-        let path = "/build";
         let query = opts.serialize();
+        let registry_config = opts.registry_config();
+        let supported = require_version(&self.interact, opts.required_api_version());
 
-        future::result(tarball::dir(&mut bytes, &opts.path[..])).and_then(move |_| {
-            let body = Some(Body::from(bytes));
+        future::result(supported.and_then(|_| context.into_body(opts)))
+            .map(move |body| {
+                let path = "/build";
+                let mut args = (path, query.as_deref(), Some(body)).into_request_args();
+                args.set_header(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static("application/x-tar"),
+                );
+                if let Some(ref config) = registry_config {
+                    args.set_registry_header(REGISTRY_CONFIG_HEADER, config);
+                }
 
-            let args = (path, query.as_slice(), body);
-            parse_to_trait::<Vec<Top>>(interact.get(args))
-        })
+                parse_to_checked_stream::<BuildOutput>(interact.post(args))
+            }).flatten_stream()
     }
 
     /// Lists the docker images on the current docker host
@@ -55,7 +82,7 @@ impl Images {
         let path = "/images/json";
         let query = opts.serialize();
 
-        let args = (path, query.as_slice());
+        let args = (path, query.as_deref());
 
         parse_to_trait::<Vec<ImageRep>>(self.interact.get(args))
     }
@@ -68,19 +95,39 @@ impl Images {
         let path = "/images/search";
         let query = build_simple_query("term", Some(term));
 
-        let args = (path, query.as_slice());
+        let args = (path, query.as_deref());
 
         parse_to_trait::<Vec<SearchResult>>(self.interact.get(args))
     }
 
-    /// Pull and create a new docker images from an existing image
-    pub fn pull(&self, opts: &PullOptions) -> impl Future<Item = Value, Error = Error> + Send {
+    /// Pull and create a new docker images from an existing image, streaming the
+    /// progress reported by the daemon. Errors reported in the middle of the pull
+    /// end the stream with `ErrorKind::StreamError`.
+    pub fn pull(
+        &self,
+        opts: &PullOptions,
+    ) -> impl Stream<Item = PullProgress, Error = Error> + Send {
         let path = "/images/create";
         let query = opts.serialize();
 
-        let args = (path, query.as_slice());
+        let mut args = (path, query.as_deref()).into_request_args();
+        if let Some(auth) = opts.auth() {
+            args.set_registry_header(REGISTRY_AUTH_HEADER, &auth.serialize());
+        }
+
+        parse_to_checked_stream::<PullProgress>(self.interact.post(args))
+    }
 
-        parse_to_trait::<Value>(self.interact.post(args))
+    /// Pulls an image to completion, resolving to the digest it was pulled at.
+    /// Images imported from a source rather than a registry don't have one.
+    pub fn pull_digest(
+        &self,
+        opts: &PullOptions,
+    ) -> impl Future<Item = Option<String>, Error = Error> + Send {
+        self.pull(opts).fold(None, |digest, progress| {
+            let digest = progress.digest().map(String::from).or(digest);
+            Ok::<_, Error>(digest)
+        })
     }
 
     /// exports a collection of named images,
@@ -93,7 +140,7 @@ impl Images {
 
         let path = "/images/get";
         let query = Some(form_urlencoded::serialize(params));
-        let args = (path, query.as_slice());
+        let args = (path, query.as_deref());
 
         parse_to_lines(self.interact.get(args))
     }
@@ -102,3 +149,120 @@ impl Images {
     //  self.interact.post
     // }
 }
+
+#[cfg(test)]
+mod tests {
+    use communicate::new_docker_with_transport;
+    use futures::Future;
+    use futures::Stream;
+    use hyper::Method;
+    use std::sync::Arc;
+    use transport::mock::MockDocker;
+    use ApiVersion;
+    use BuildContext;
+    use BuildOptions;
+    use ErrorKind;
+    use PullOptions;
+
+    #[test]
+    fn pull_progress() {
+        let digest = "sha256:e1871801d30885a610511c867de0d6baca7ed4e6a2573d506bbec7fd3b03873f";
+
+        let mut mock = MockDocker::new();
+        mock.expect(Method::POST, "/images/create")
+            .query("fromImage=alpine")
+            .times(2)
+            .chunk(concat!(r#"{"status":"Pulling from library/alpine","id":"latest"}"#, "\n"))
+            .chunk(r#"{"status":"Downloading","progressDetail":{"current":512,"#)
+            .chunk(concat!(r#""total":2048},"id":"4fe2ade4980c"}"#, "\n"))
+            .chunk(format!("{{\"status\":\"Digest: {}\"}}\n", digest))
+            .chunk(concat!(
+                r#"{"status":"Status: Downloaded newer image for alpine:latest"}"#,
+                "\n"
+            ));
+
+        let docker = new_docker_with_transport(Arc::new(mock));
+        let opts = PullOptions::builder().image("alpine").build();
+
+        let progress = docker
+            .images()
+            .pull(&opts)
+            .collect()
+            .wait()
+            .expect("Pull failed");
+        assert_eq!(progress.len(), 4);
+        let detail = progress[1].progressDetail.as_ref().expect("No progress");
+        assert_eq!((detail.current, detail.total), (Some(512), Some(2048)));
+
+        let pulled = docker
+            .images()
+            .pull_digest(&opts)
+            .wait()
+            .expect("Pull failed");
+        assert_eq!(pulled.as_deref(), Some(digest));
+    }
+
+    #[test]
+    fn pull_stream_error() {
+        let mut mock = MockDocker::new();
+        mock.expect(Method::POST, "/images/create")
+            .chunk(concat!(r#"{"status":"Pulling from library/missing","id":"latest"}"#, "\n"))
+            .chunk(concat!(
+                r#"{"errorDetail":{"message":"manifest unknown"},"error":"manifest unknown"}"#,
+                "\n"
+            ));
+
+        let docker = new_docker_with_transport(Arc::new(mock));
+        let opts = PullOptions::builder().image("missing").build();
+
+        let err = docker
+            .images()
+            .pull(&opts)
+            .collect()
+            .wait()
+            .expect_err("Pull succeeded");
+        match *err.kind() {
+            ErrorKind::StreamError(ref message) => assert_eq!(message, "manifest unknown"),
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn build_output() {
+        let mut mock = MockDocker::new();
+        mock.expect(Method::POST, "/build")
+            .query("t=app")
+            .chunk(concat!(r#"{"stream":"Step 1/2 : FROM alpine"}"#, "\n"))
+            .chunk(concat!(r#"{"stream":"\n"}"#, "\n", r#"{"aux":{"ID":"sha256:4a"}}"#))
+            .chunk(concat!("\n", r#"{"stream":"Successfully built 4a\n"}"#, "\n"));
+        mock.set_api_version(ApiVersion::new(1, 28));
+
+        let docker = new_docker_with_transport(Arc::new(mock));
+        let context = BuildContext::files(vec![("Dockerfile", "FROM alpine\n")]);
+        let opts = BuildOptions::builder(".").tag("app").build();
+
+        let output = docker
+            .images()
+            .build_with_context(&opts, context)
+            .collect()
+            .wait()
+            .expect("Build failed");
+        assert_eq!(output.len(), 4);
+        assert_eq!(output[0].stream.as_deref(), Some("Step 1/2 : FROM alpine"));
+        assert_eq!(output[2].aux.as_ref().map(|aux| aux.ID.as_str()), Some("sha256:4a"));
+
+        let opts = BuildOptions::builder(".").target("release").build();
+        let err = docker
+            .images()
+            .build_with_context(&opts, BuildContext::tar(Vec::new()))
+            .collect()
+            .wait()
+            .expect_err("Build succeeded");
+        match *err.kind() {
+            ErrorKind::UnsupportedApiVersion(feature, ..) => {
+                assert_eq!(feature, "BuildOptionsBuilder::target")
+            }
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+    }
+}
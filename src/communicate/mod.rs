@@ -1,17 +1,28 @@
 pub mod container;
 pub mod containers;
 pub mod docker;
+pub mod exec;
 pub mod image;
 pub mod images;
 pub mod network;
 pub mod networks;
+mod rustls_tcp_docker;
 mod ssl_tcp_docker;
 mod tcp_docker;
+pub mod tls;
 mod unix_docker;
 pub mod util;
+pub mod version;
+pub mod volume;
+pub mod volumes;
 
 pub use container::Container;
-pub use docker::{new_docker, DockerApi};
+pub use docker::{new_docker, new_docker_with_transport, parse_host, DockerApi, DockerBuilder};
+pub use exec::Exec;
 pub use image::Image;
 pub use images::Images;
 pub use network::Network;
+pub use tls::TlsConfig;
+pub use version::{ApiVersion, CLIENT_API_VERSION};
+pub use volume::Volume;
+pub use volumes::Volumes;
@@ -1,7 +1,7 @@
 use build::ContainerConnectionOptions;
-use communicate::util::AsSlice;
 use futures::Future;
 use http::StatusCode;
+use hyper::Body;
 use representation::rep::NetworkDetails;
 use std::borrow::Cow;
 use std::sync::Arc;
@@ -54,10 +54,10 @@ impl<'b> Network<'b> {
         opts: &ContainerConnectionOptions,
     ) -> impl Future<Item = StatusCode, Error = Error> {
         let path = format!("/networks/{}/connect", self.id);
-        let query = opts.serialize();
-        let args = (path.as_str(), query.as_slice());
+        let body = opts.serialize().map(Body::from);
+        let args = (path.as_str(), body);
 
-        status_code(self.interact.post(args))
+        status_code(self.interact.post_json(args))
     }
 
     /// Disconnect container to network
@@ -66,9 +66,38 @@ impl<'b> Network<'b> {
         opts: &ContainerConnectionOptions,
     ) -> impl Future<Item = StatusCode, Error = Error> {
         let path = format!("/networks/{}/disconnect", self.id);
-        let query = opts.serialize();
-        let args = (path.as_str(), query.as_slice());
+        let body = opts.serialize().map(Body::from);
+        let args = (path.as_str(), body);
 
-        status_code(self.interact.post(args))
+        status_code(self.interact.post_json(args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use communicate::new_docker_with_transport;
+    use futures::Future;
+    use hyper::Method;
+    use std::borrow::Cow;
+    use std::sync::Arc;
+    use transport::mock::MockDocker;
+    use ContainerConnectionOptions;
+
+    #[test]
+    fn connect_options_body() {
+        let opts = ContainerConnectionOptions::new("web");
+        assert_eq!(opts.serialize().as_deref(), Some(r#"{"Container":"web"}"#));
+
+        let mut mock = MockDocker::new();
+        mock.expect(Method::POST, "/networks/backend/connect").query("");
+        mock.expect(Method::POST, "/networks/backend/disconnect").query("");
+
+        let mock = Arc::new(mock);
+        let docker = new_docker_with_transport(mock.clone());
+        let network = docker.network(Cow::from("backend"));
+
+        network.connect(&opts).wait().expect("Connect failed");
+        network.disconnect(&opts).wait().expect("Disconnect failed");
+        mock.verify();
     }
 }
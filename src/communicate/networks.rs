@@ -1,6 +1,5 @@
 use build::NetworkCreateOptions;
 use build::NetworkListOptions;
-use communicate::util::AsSlice;
 use futures::Future;
 use hyper::Body;
 use representation::rep::NetworkCreateInfo;
@@ -29,7 +28,7 @@ impl Networks {
     ) -> impl Future<Item = Vec<NetworkDetails>, Error = Error> {
         let path = "/networks";
         let query = opts.serialize();
-        let args = (path, query.as_slice());
+        let args = (path, query.as_deref());
 
         parse_to_trait::<Vec<NetworkDetails>>(self.interact.get(args))
     }
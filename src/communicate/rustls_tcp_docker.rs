@@ -0,0 +1,146 @@
+#![cfg(feature = "rustls")]
+
+use communicate::docker::Docker;
+use communicate::tls::TlsConfig;
+use communicate::version::ApiVersion;
+use errors::ErrorKind;
+use errors::Result;
+use hyper::client::HttpConnector;
+use hyper::Client;
+use hyper::Uri;
+use rustls::client::ServerCertVerified;
+use rustls::client::ServerCertVerifier;
+use rustls::client::WebPkiVerifier;
+use rustls::Certificate;
+use rustls::CertificateError;
+use rustls::ClientConfig;
+use rustls::OwnedTrustAnchor;
+use rustls::PrivateKey;
+use rustls::RootCertStore;
+use rustls::ServerName;
+use rustls_pemfile;
+use rustls_pemfile::Item;
+use std::sync::Arc;
+use std::time::SystemTime;
+use transport::interact::Interact;
+use transport::interact::InteractApi;
+use transport::rustls_connector::RustlsConnector;
+use webpki_roots::TLS_SERVER_ROOTS;
+
+pub(crate) type TcpRustlsDocker = Docker<RustlsConnector>;
+
+const THREADS: usize = 1;
+
+impl Docker<RustlsConnector> {
+    pub(crate) fn transport(
+        host: Uri,
+        version: Option<ApiVersion>,
+        tls: Option<TlsConfig>,
+    ) -> Result<Arc<InteractApi>> {
+        let tls = match tls {
+            Some(tls) => tls,
+            None => TlsConfig::from_env()?,
+        };
+
+        let mut http = HttpConnector::new(THREADS);
+        http.enforce_http(false);
+
+        let connector = RustlsConnector::new(http, configure(&tls)?, tls.server_name.clone());
+        let client = Client::builder().build(connector);
+
+        Ok(Arc::new(Interact::new(client, host, version)))
+    }
+}
+
+fn configure(tls: &TlsConfig) -> Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    match tls.ca {
+        Some(ref ca) => {
+            let certs = rustls_pemfile::certs(&mut &ca.load()?[..])
+                .map_err(|_| ErrorKind::TlsMaterial("invalid CA bundle".to_string()))?;
+            let (valid, _) = roots.add_parsable_certificates(&certs);
+            if valid == 0 {
+                return Err(ErrorKind::TlsMaterial("empty CA bundle".to_string()).into());
+            }
+        }
+        None => roots.add_trust_anchors(TLS_SERVER_ROOTS.iter().map(|anchor| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(
+                anchor.subject,
+                anchor.spki,
+                anchor.name_constraints,
+            )
+        })),
+    }
+
+    let builder = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots.clone());
+    let mut config = match tls.identity()? {
+        Some((cert, key)) => {
+            let certs = rustls_pemfile::certs(&mut &cert[..])
+                .map_err(|_| ErrorKind::TlsMaterial("invalid client certificate".to_string()))?
+                .into_iter()
+                .map(Certificate)
+                .collect();
+            builder
+                .with_client_auth_cert(certs, private_key(&key)?)
+                .map_err(|err| ErrorKind::TlsMaterial(err.to_string()))?
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    if !tls.verify_hostname {
+        let verifier = WebPkiVerifier::new(roots, None);
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(NoHostnameVerifier(verifier)));
+    }
+
+    Ok(config)
+}
+
+/// Reads the first PKCS#8, RSA or EC private key of a PEM document
+fn private_key(pem: &[u8]) -> Result<PrivateKey> {
+    let items = rustls_pemfile::read_all(&mut &pem[..])
+        .map_err(|_| ErrorKind::TlsMaterial("invalid client key".to_string()))?;
+
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        }).next()
+        .ok_or_else(|| ErrorKind::TlsMaterial("invalid client key".to_string()).into())
+}
+
+/// Verifies the daemon certificate like rustls does, except for the host name it was
+/// issued for, which webpki checks last
+struct NoHostnameVerifier(WebPkiVerifier);
+
+impl ServerCertVerifier for NoHostnameVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> ::std::result::Result<ServerCertVerified, rustls::Error> {
+        let verified = self.0.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        );
+
+        match verified {
+            Err(rustls::Error::InvalidCertificate(CertificateError::NotValidForName)) => {
+                Ok(ServerCertVerified::assertion())
+            }
+            verified => verified,
+        }
+    }
+}
@@ -5,54 +5,84 @@ extern crate openssl;
 use self::hyper_openssl::HttpsConnector;
 use self::openssl::ssl::SslMethod;
 
+use self::openssl::pkey::PKey;
 use self::openssl::ssl::SslContextBuilder;
+use self::openssl::x509::X509;
 use communicate::docker::Docker;
-use communicate::docker::DockerApi;
+use communicate::tls::TlsConfig;
+use communicate::version::ApiVersion;
 use errors::Result;
 use hyper::client::HttpConnector;
 use hyper::Client;
 use hyper::Uri;
-use std::env;
-use std::path::Path;
 use std::sync::Arc;
 use transport::interact::Interact;
+use transport::interact::InteractApi;
 use Error;
 use self::openssl::ssl::SslConnector;
-use self::openssl::ssl::SslFiletype;
 
 pub(crate) type TcpSSLDocker = Docker<HttpsConnector<HttpConnector>>;
 
 const THREADS: usize = 1;
 
 impl Docker<HttpsConnector<HttpConnector>> {
-    pub(crate) fn new(host: Uri) -> Result<Box<DockerApi>> {
-        let certs = env::var("DOCKER_CERT_PATH").ok().expect("No SSL cert");
-
-        let cert = &format!("{}/cert.pem", certs);
-        let key = &format!("{}/key.pem", certs);
+    pub(crate) fn transport(
+        host: Uri,
+        version: Option<ApiVersion>,
+        tls: Option<TlsConfig>,
+    ) -> Result<Arc<InteractApi>> {
+        let tls = match tls {
+            Some(tls) => tls,
+            None => TlsConfig::from_env()?,
+        };
 
         // https://github.com/hyperium/hyper/blob/master/src/net.rs#L427-L428
-        let mut connector = SslConnector::builder(SslMethod::tls())
-            .expect("Error during ssl connection preparation");
+        let mut connector = SslConnector::builder(SslMethod::tls())?;
 
         connector.set_cipher_list("DEFAULT")?;
-        connector.set_certificate_file(&Path::new(cert), SslFiletype::PEM)?;
-        connector.set_private_key_file(&Path::new(key), SslFiletype::PEM)?;
-
-        if let Some(_) = env::var("DOCKER_TLS_VERIFY").ok() {
-            let ca = &format!("{}/ca.pem", certs);
-            connector.set_ca_file(&Path::new(ca))?;
-        }
+        configure(&mut connector, &tls)?;
 
         let mut http = HttpConnector::new(THREADS);
         http.enforce_http(false);
 
-        let connector = HttpsConnector::<HttpConnector>::with_connector(http, connector)
+        let mut connector = HttpsConnector::<HttpConnector>::with_connector(http, connector)
             .map_err(Error::from)?;
 
+        let verify_hostname = tls.verify_hostname;
+        let server_name = tls.server_name.clone();
+        connector.set_callback(move |ssl, _| {
+            ssl.set_verify_hostname(verify_hostname);
+            if let Some(ref name) = server_name {
+                ssl.set_use_server_name_indication(false);
+                ssl.set_hostname(name)?;
+                if verify_hostname {
+                    ssl.set_verify_hostname(false);
+                    ssl.param_mut().set_host(name)?;
+                }
+            }
+            Ok(())
+        });
+
         let client = Client::builder().build(connector);
 
-        let docker = Self::new_inner(Arc::new(Interact::new(client, host)));
-        Ok(Box::new(docker))
+        Ok(Arc::new(Interact::new(client, host, version)))
     }
 }
+
+fn configure(connector: &mut SslContextBuilder, tls: &TlsConfig) -> Result<()> {
+    if let Some((cert, key)) = tls.identity()? {
+        let cert = X509::from_pem(&cert)?;
+        let key = PKey::private_key_from_pem(&key)?;
+        connector.set_certificate(&cert)?;
+        connector.set_private_key(&key)?;
+        connector.check_private_key()?;
+    }
+
+    if let Some(ref ca) = tls.ca {
+        for cert in X509::stack_from_pem(&ca.load()?)? {
+            connector.cert_store_mut().add_cert(cert)?;
+        }
+    }
+
+    Ok(())
+}
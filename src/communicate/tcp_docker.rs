@@ -3,18 +3,16 @@ use hyper::Client;
 use hyper::Uri;
 
 use communicate::docker::Docker;
-use communicate::docker::DockerApi;
+use communicate::version::ApiVersion;
 use errors::Result;
 use std::sync::Arc;
 use transport::interact::Interact;
+use transport::interact::InteractApi;
 
 pub(super) type TcpDocker = Docker<HttpConnector>;
 
 impl Docker<HttpConnector> {
-    pub(crate) fn new(host: Uri) -> Result<Box<DockerApi>> {
-        let interact = Interact::new(Client::new(), host);
-        let docker = Self::new_inner(Arc::new(interact));
-
-        Ok(Box::new(docker))
+    pub(crate) fn transport(host: Uri, version: Option<ApiVersion>) -> Result<Arc<InteractApi>> {
+        Ok(Arc::new(Interact::new(Client::new(), host, version)))
     }
 }
@@ -0,0 +1,165 @@
+//! TLS material used to connect to a docker daemon over `https`
+
+use errors::ErrorKind;
+use errors::Result;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// PEM encoded material, either held in memory or read from a file when connecting
+#[derive(Clone, Debug)]
+pub enum Pem {
+    Bytes(Vec<u8>),
+    Path(PathBuf),
+}
+
+impl Pem {
+    pub fn load(&self) -> Result<Vec<u8>> {
+        match *self {
+            Pem::Bytes(ref bytes) => Ok(bytes.clone()),
+            Pem::Path(ref path) => fs::read(path).map_err(|err| {
+                ErrorKind::TlsMaterial(format!("{}: {}", path.display(), err)).into()
+            }),
+        }
+    }
+}
+
+/// TLS configuration of the docker client
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    pub(crate) cert: Option<Pem>,
+    pub(crate) key: Option<Pem>,
+    pub(crate) ca: Option<Pem>,
+    pub(crate) verify_hostname: bool,
+    pub(crate) server_name: Option<String>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> TlsConfig {
+        TlsConfig {
+            cert: None,
+            key: None,
+            ca: None,
+            verify_hostname: true,
+            server_name: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    pub fn new() -> TlsConfig {
+        TlsConfig {
+            ..Default::default()
+        }
+    }
+
+    /// Reads `cert.pem`, `key.pem` and, when `DOCKER_TLS_VERIFY` is set, `ca.pem`
+    /// from `DOCKER_CERT_PATH`, like the docker CLI
+    pub fn from_env() -> Result<TlsConfig> {
+        let certs = env::var("DOCKER_CERT_PATH")
+            .map_err(|_| ErrorKind::TlsMaterial("DOCKER_CERT_PATH is not set".to_string()))?;
+        let certs = Path::new(&certs);
+
+        let mut config = TlsConfig::new();
+        config
+            .client_cert_path(certs.join("cert.pem"))
+            .client_key_path(certs.join("key.pem"));
+
+        if env::var("DOCKER_TLS_VERIFY").map(|v| !v.is_empty()).unwrap_or(false) {
+            config.ca_path(certs.join("ca.pem"));
+        }
+
+        Ok(config)
+    }
+
+    /// PEM encoded client certificate
+    pub fn client_cert(&mut self, pem: Vec<u8>) -> &mut TlsConfig {
+        self.cert = Some(Pem::Bytes(pem));
+        self
+    }
+
+    pub fn client_cert_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut TlsConfig {
+        self.cert = Some(Pem::Path(path.into()));
+        self
+    }
+
+    /// PEM encoded private key of the client certificate
+    pub fn client_key(&mut self, pem: Vec<u8>) -> &mut TlsConfig {
+        self.key = Some(Pem::Bytes(pem));
+        self
+    }
+
+    pub fn client_key_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut TlsConfig {
+        self.key = Some(Pem::Path(path.into()));
+        self
+    }
+
+    /// PEM encoded bundle of certificate authorities trusted for the daemon certificate
+    pub fn ca(&mut self, pem: Vec<u8>) -> &mut TlsConfig {
+        self.ca = Some(Pem::Bytes(pem));
+        self
+    }
+
+    pub fn ca_path<P: Into<PathBuf>>(&mut self, path: P) -> &mut TlsConfig {
+        self.ca = Some(Pem::Path(path.into()));
+        self
+    }
+
+    /// Whether the daemon certificate has to match the host name, enabled by default
+    pub fn verify_hostname(&mut self, verify: bool) -> &mut TlsConfig {
+        self.verify_hostname = verify;
+        self
+    }
+
+    /// Name sent as SNI and verified against the daemon certificate instead of the host
+    pub fn server_name<S: Into<String>>(&mut self, name: S) -> &mut TlsConfig {
+        self.server_name = Some(name.into());
+        self
+    }
+
+    /// Loads the client certificate and key, which have to be provided together
+    #[cfg_attr(not(any(feature = "ssl", feature = "rustls")), allow(dead_code))]
+    pub(crate) fn identity(&self) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        match (self.cert.as_ref(), self.key.as_ref()) {
+            (Some(cert), Some(key)) => Ok(Some((cert.load()?, key.load()?))),
+            (None, None) => Ok(None),
+            (Some(_), None) => {
+                Err(ErrorKind::TlsMaterial("client key is missing".to_string()).into())
+            }
+            (None, Some(_)) => {
+                Err(ErrorKind::TlsMaterial("client certificate is missing".to_string()).into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TlsConfig;
+
+    #[test]
+    fn tls_identity_requires_cert_and_key() {
+        let mut config = TlsConfig::new();
+        assert!(config.identity().expect("No identity").is_none());
+
+        config.client_cert(b"cert".to_vec());
+        assert!(config.identity().is_err());
+
+        config.client_key(b"key".to_vec());
+        assert_eq!(
+            config.identity().expect("No identity"),
+            Some((b"cert".to_vec(), b"key".to_vec()))
+        );
+    }
+
+    #[test]
+    fn tls_missing_file() {
+        let mut config = TlsConfig::new();
+        config
+            .client_cert_path("/nonexistent/cert.pem")
+            .client_key_path("/nonexistent/key.pem");
+
+        assert!(config.identity().is_err());
+    }
+}
@@ -17,11 +17,12 @@ use tokio::io;
 use tokio::prelude::future;
 use tokio::prelude::Future;
 
-use communicate::docker::DockerApi;
+use communicate::version::ApiVersion;
 use docker::Docker;
 use errors::Result;
 use std::sync::Arc;
 use transport::interact::Interact;
+use transport::interact::InteractApi;
 
 pub struct UnixConnector {
     path: PathBuf,
@@ -58,26 +59,23 @@ impl Connect for UnixConnector {
 pub(crate) type UnixDocker = Docker<UnixConnector>;
 
 impl Docker<UnixConnector> {
-    pub(crate) fn new(host: Uri) -> Result<Box<DockerApi>> {
-        let path = format!(
-            "/{}{}",
-            host.authority_part()
-                .map(|a| a.as_str())
-                .unwrap_or_default(),
-            host.path()
-        );
+    pub(crate) fn transport(host: Uri, version: Option<ApiVersion>) -> Result<Arc<InteractApi>> {
+        let path = match host.authority_part().map(|a| a.as_str()) {
+            Some("localhost") | None => host.path().to_string(),
+            Some(authority) => format!("/{}{}", authority, host.path()),
+        };
         let mut parts = host.into_parts();
         parts.authority =
-            Some(Authority::from_str("v1.37").expect("Constant authority parsing error"));
+            Some(Authority::from_str("localhost").expect("Constant authority parsing error"));
         parts.scheme = Some(Scheme::from_str("http").expect("Constant scheme parsing error"));
 
         let host = Uri::from_parts(parts)?;
         let interact = Interact::new(
             Client::builder().build(UnixConnector::new(PathBuf::from(path))),
             host,
+            version,
         );
 
-        let docker = Self::new_inner(Arc::new(interact));
-        Ok(Box::new(docker))
+        Ok(Arc::new(interact))
     }
 }
@@ -13,7 +13,15 @@ use std::str;
 use url::form_urlencoded;
 
 pub(crate) const URI_ENV: &'static str = "SHIPLIFT_URI";
-pub(crate) const DEFAULT_URI: &'static str = "unix://var/run/docker.sock";
+pub(crate) const HOST_ENV: &'static str = "DOCKER_HOST";
+pub(crate) const TLS_VERIFY_ENV: &'static str = "DOCKER_TLS_VERIFY";
+pub(crate) const API_VERSION_ENV: &'static str = "DOCKER_API_VERSION";
+pub(crate) const DEFAULT_URI: &'static str = "unix:///var/run/docker.sock";
+pub(crate) const REGISTRY_AUTH_HEADER: &'static str = "x-registry-auth";
+pub(crate) const REGISTRY_CONFIG_HEADER: &'static str = "x-registry-config";
+pub(crate) const PATH_STAT_HEADER: &'static str = "x-docker-container-path-stat";
+/// Base64 encoded empty credentials, the daemon rejects pushes without any
+pub(crate) const ANONYMOUS_REGISTRY_AUTH: &'static str = "e30=";
 
 pub(crate) fn build_simple_query<A>(name: &str, value: Option<A>) -> Option<String>
 where
@@ -28,21 +36,9 @@ where
     query
 }
 
-pub(crate) trait AsSlice {
-    fn as_slice(&self) -> Option<&str>;
-}
-
-impl AsSlice for Option<String> {
-    fn as_slice(&self) -> Option<&str> {
-        match self {
-            Some(ref x) => Some(x),
-            None => None,
-        }
-    }
-}
-
+/// Request sent through an `InteractApi` transport, the path excludes the API version prefix
 #[derive(Default)]
-pub(crate) struct RequestArgs<'a, 'b> {
+pub struct RequestArgs<'a, 'b> {
     pub path: &'a str,
     pub query: &'b str,
     pub body: Body,
@@ -57,12 +53,24 @@ impl<'a, 'b> RequestArgs<'a, 'b> {
     {
         self.header.insert(key.into(), value.into());
     }
+
+    /// Sets one of the headers carrying base64 encoded registry credentials
+    pub(crate) fn set_registry_header(&mut self, name: &'static str, value: &str) {
+        let value = HeaderValue::from_str(value).expect("Base64 header value parsing failed");
+        self.set_header(HeaderName::from_static(name), value);
+    }
 }
 
 pub(crate) trait IntoRequestArgs<'a, 'b> {
     fn into_request_args(self) -> RequestArgs<'a, 'b>;
 }
 
+impl<'a, 'b> IntoRequestArgs<'a, 'b> for RequestArgs<'a, 'b> {
+    fn into_request_args(self) -> RequestArgs<'a, 'b> {
+        self
+    }
+}
+
 impl<'a, 'b> IntoRequestArgs<'a, 'b> for &'a str {
     fn into_request_args(self) -> RequestArgs<'a, 'b> {
         let mut args = RequestArgs::default();
@@ -0,0 +1,100 @@
+use errors::ErrorKind;
+use std::fmt;
+use std::str::FromStr;
+use transport::interact::InteractApi;
+use Error;
+use Result;
+
+/// Version of the docker engine API, e.g. `1.37`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion {
+    major: u32,
+    minor: u32,
+}
+
+/// The most recent API version this client knows how to talk
+pub const CLIENT_API_VERSION: ApiVersion = ApiVersion {
+    major: 1,
+    minor: 41,
+};
+
+impl ApiVersion {
+    pub fn new(major: u32, minor: u32) -> ApiVersion {
+        ApiVersion { major, minor }
+    }
+
+    pub fn major(&self) -> u32 {
+        self.major
+    }
+
+    pub fn minor(&self) -> u32 {
+        self.minor
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = Error;
+
+    /// Parses versions in the `1.37` or `v1.37` format
+    fn from_str(s: &str) -> Result<ApiVersion> {
+        let trimmed = s.trim();
+        let trimmed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+
+        let mut parts = trimmed.splitn(2, '.');
+        let major = parts.next().and_then(|p| p.parse().ok());
+        let minor = parts.next().and_then(|p| p.parse().ok());
+
+        match (major, minor) {
+            (Some(major), Some(minor)) => Ok(ApiVersion::new(major, minor)),
+            _ => Err(ErrorKind::InvalidApiVersion(s.to_owned()).into()),
+        }
+    }
+}
+
+/// Fails when the API version in use is known to be older than `required`.
+/// Unversioned clients are served by the daemon's own latest version, so they always pass.
+/// Only options added after API 1.25 (Docker 1.13) declare a required version, older
+/// ones are assumed to be supported by every daemon.
+pub(crate) fn require_version(
+    interact: &InteractApi,
+    required: Option<(ApiVersion, &'static str)>,
+) -> Result<()> {
+    match (interact.api_version(), required) {
+        (Some(current), Some((required, feature))) if current < required => {
+            Err(ErrorKind::UnsupportedApiVersion(feature, required, current).into())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiVersion;
+
+    #[test]
+    fn parse_api_version() {
+        assert_eq!(
+            "1.37".parse::<ApiVersion>().expect("Parse failed"),
+            ApiVersion::new(1, 37)
+        );
+        assert_eq!(
+            "v1.9".parse::<ApiVersion>().expect("Parse failed"),
+            ApiVersion::new(1, 9)
+        );
+        assert!("1".parse::<ApiVersion>().is_err());
+        assert!("latest".parse::<ApiVersion>().is_err());
+    }
+
+    #[test]
+    fn compare_api_version() {
+        assert!(ApiVersion::new(1, 9) < ApiVersion::new(1, 30));
+        assert!(ApiVersion::new(2, 0) > ApiVersion::new(1, 43));
+        assert_eq!(ApiVersion::new(1, 30).to_string(), "1.30");
+    }
+}
@@ -0,0 +1,51 @@
+use futures::Future;
+use http::StatusCode;
+use representation::rep::VolumeDetails;
+use std::borrow::Cow;
+use std::sync::Arc;
+use transport::interact::InteractApi;
+use transport::interact::InteractApiExt;
+use transport::parse::parse_to_trait;
+use transport::parse::status_code;
+use util::build_simple_query;
+use Error;
+
+/// Interface for accessing and manipulating a docker volume
+pub struct Volume<'b> {
+    interact: Arc<InteractApi>,
+    name: Cow<'b, str>,
+}
+
+impl<'b> Volume<'b> {
+    /// Exports an interface exposing operations against a volume instance
+    pub(crate) fn new<S>(interact: Arc<InteractApi>, name: S) -> Volume<'b>
+    where
+        S: Into<Cow<'b, str>>,
+    {
+        Volume {
+            interact,
+            name: name.into(),
+        }
+    }
+
+    /// a getter for the Volume name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Inspects the current docker volume instance's details
+    pub fn inspect(&self) -> impl Future<Item = VolumeDetails, Error = Error> {
+        let path = format!("/volumes/{}", self.name);
+
+        parse_to_trait::<VolumeDetails>(self.interact.get(path.as_str()))
+    }
+
+    /// Delete the volume instance, `force` also removes a volume that is in use
+    pub fn delete(&self, force: bool) -> impl Future<Item = StatusCode, Error = Error> {
+        let path = format!("/volumes/{}", self.name);
+        let query = build_simple_query("force", Some(force.to_string()));
+        let args = (path.as_str(), query.as_deref());
+
+        status_code(self.interact.delete(args))
+    }
+}
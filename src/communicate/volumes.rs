@@ -0,0 +1,53 @@
+use build::VolumeCreateOptions;
+use build::VolumeListOptions;
+use futures::Future;
+use hyper::Body;
+use representation::rep::VolumeDetails;
+use representation::rep::VolumeList;
+use representation::rep::VolumePruneInfo;
+use std::sync::Arc;
+use transport::interact::InteractApi;
+use transport::interact::InteractApiExt;
+use transport::parse::parse_to_trait;
+use Error;
+
+/// Interface for docker volumes
+pub struct Volumes {
+    interact: Arc<InteractApi>,
+}
+
+impl Volumes {
+    /// Exports an interface for interacting with docker volumes
+    pub(crate) fn new(interact: Arc<InteractApi>) -> Volumes {
+        Volumes { interact }
+    }
+
+    /// List the docker volumes on the current docker host
+    pub fn list(&self, opts: &VolumeListOptions) -> impl Future<Item = VolumeList, Error = Error> {
+        let path = "/volumes";
+        let query = opts.serialize();
+        let args = (path, query.as_deref());
+
+        parse_to_trait::<VolumeList>(self.interact.get(args))
+    }
+
+    /// Create a new docker volume
+    pub fn create(
+        &self,
+        opts: &VolumeCreateOptions,
+    ) -> impl Future<Item = VolumeDetails, Error = Error> {
+        let path = "/volumes/create";
+        let bytes = opts.serialize().expect("Error during serialization");
+        let body = Some(Body::from(bytes));
+        let args = (path, body);
+
+        parse_to_trait::<VolumeDetails>(self.interact.post_json(args))
+    }
+
+    /// Delete all unused volumes
+    pub fn prune(&self) -> impl Future<Item = VolumePruneInfo, Error = Error> {
+        let path = "/volumes/prune";
+
+        parse_to_trait::<VolumePruneInfo>(self.interact.post(path))
+    }
+}
@@ -1,5 +1,6 @@
 //! Representations of various client errors
 
+use communicate::version::ApiVersion;
 use hyper::StatusCode;
 
 error_chain! {
@@ -21,6 +22,7 @@ error_chain! {
         InvalidHttpHeaderName(::hyper::header::InvalidHeaderName);
         InvalidHttpHeaderValue(::hyper::header::InvalidHeaderValue);
         StripPrefixError(::std::path::StripPrefixError);
+        Base64(::base64::DecodeError);
     }
 
     errors {
@@ -34,6 +36,61 @@ error_chain! {
                 display("{}", code)
         }
 
+        NotModified(status: StatusCode, message: String) {
+            description("Resource not modified")
+                display("{}: {}", status, message)
+        }
+
+        BadParameter(status: StatusCode, message: String) {
+            description("Bad parameter")
+                display("{}: {}", status, message)
+        }
+
+        NotFound(status: StatusCode, message: String) {
+            description("No such resource")
+                display("{}: {}", status, message)
+        }
+
+        Conflict(status: StatusCode, message: String) {
+            description("Conflicting resource state")
+                display("{}: {}", status, message)
+        }
+
+        ServerError(status: StatusCode, message: String) {
+            description("Docker daemon error")
+                display("{}: {}", status, message)
+        }
+
+        UnexpectedStatus(status: StatusCode, message: String) {
+            description("Unexpected response status")
+                display("{}: {}", status, message)
+        }
+
+        StreamError(message: String) {
+            description("Error reported in a streamed response")
+                display("{}", message)
+        }
+
+        ExecFailed(exit_code: i64, stderr: String) {
+            description("Exec command failed")
+                display("Exec command exited with code {}: {}", exit_code, stderr)
+        }
+
+        ExecExitCodeUnknown(id: String) {
+            description("Exec exit code unknown")
+                display("Exit code of exec instance {} is unknown", id)
+        }
+
+        InvalidApiVersion(version: String) {
+            description("Invalid API version")
+                display("Invalid API version '{}', expected the '1.37' format", version)
+        }
+
+        UnsupportedApiVersion(feature: &'static str, required: ApiVersion, current: ApiVersion) {
+            description("Option not supported by the API version in use")
+                display("{} requires API version {}, but {} is in use", feature, required, current)
+        }
+
         Utf8 {
             description("Error while trying to handle non-utf8 string")
                 display("Error while trying to handle non-utf8 string")
@@ -44,6 +101,16 @@ error_chain! {
                 display("JSON Field '{}' missing", name)
         }
 
+        HeaderMissing(name: &'static str) {
+            description("Response header missing")
+                display("Response header '{}' missing", name)
+        }
+
+        UnsafeArchiveEntry(path: String) {
+            description("Archive entry escaping the destination directory")
+                display("Archive entry '{}' escapes the destination directory", path)
+        }
+
         JsonTypeError(fieldname: &'static str, expectedtype: &'static str) {
             description("JSON Field has wrong type")
                 display("JSON Field '{}' has wrong type, expected: {}", fieldname, expectedtype)
@@ -74,6 +141,16 @@ error_chain! {
                 display("Invalid uri scheme")
         }
 
+        TlsNotEnabled {
+            description("TLS requested, but no TLS backend is enabled")
+                display("TLS requested, but the crate was built without a TLS backend feature")
+        }
+
+        TlsMaterial(reason: String) {
+            description("Invalid or missing TLS material")
+                display("Invalid or missing TLS material: {}", reason)
+        }
+
         EmptyPath {
             description("Invalid path - empty parent")
                 display("Invalid uri ")
@@ -4,13 +4,26 @@
 //!
 //! ```no_run
 //! extern crate async_docker;
+//! extern crate futures;
+//! extern crate tokio;
 //!
-//! let docker = async_docker::Docker::new();
-//! let images = docker.images().list(&Default::default()).unwrap();
-//! debug!("docker images in stock");
-//! for i in images {
-//!   debug!("{:?}", i.RepoTags);
-//! }
+//! use async_docker::{new_docker, DockerApi};
+//! use futures::{future, Future};
+//!
+//! let work = future::lazy(|| {
+//!     let docker = new_docker(None).unwrap();
+//!     docker
+//!         .images()
+//!         .list(&Default::default())
+//!         .map(|images| {
+//!             println!("docker images in stock");
+//!             for i in images {
+//!                 println!("{:?}", i.RepoTags);
+//!             }
+//!         }).map_err(|e| eprintln!("{:?}", e))
+//! });
+//!
+//! tokio::runtime::run(work);
 //! ```
 
 #![recursion_limit = "256"]
@@ -22,6 +35,7 @@ extern crate serde_derive;
 #[macro_use]
 extern crate error_chain;
 
+extern crate base64;
 extern crate bytes;
 extern crate futures;
 extern crate http;
@@ -32,17 +46,36 @@ extern crate tokio;
 extern crate tokio_codec;
 extern crate url;
 
+#[cfg(feature = "rustls")]
+extern crate futures_rustls;
+#[cfg(feature = "rustls")]
+extern crate futures_util;
+#[cfg(feature = "rustls")]
+extern crate rustls;
+#[cfg(feature = "rustls")]
+extern crate rustls_pemfile;
+#[cfg(feature = "rustls")]
+extern crate webpki_roots;
+
 pub mod build;
 pub mod communicate;
 pub mod representation;
 
+#[cfg(all(any(test, feature = "testing"), target_os = "linux"))]
+pub mod testing;
+
 mod errors;
 mod tarball;
 mod transport;
 
 pub use errors::Error;
+pub use errors::ErrorKind;
 pub use errors::Result;
 
 pub use build::*;
 pub use communicate::*;
 pub use representation::*;
+pub use transport::cassette::{Cassette, RecordingTransport};
+pub use transport::interact::InteractApi;
+pub use transport::mock::{Expectation, MockDocker};
+pub use transport::ResponseFutureWrapper;
@@ -1,6 +1,8 @@
 //! Rust representations of docker json structures
 
+use serde_json::Value;
 use std::collections::HashMap;
+use std::time::SystemTime;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -54,7 +56,10 @@ pub struct Container {
     pub SizeRootFs: Option<u64>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Output of `docker inspect`. Fields unknown to older daemons are left to their zero
+/// values.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 #[allow(non_snake_case)]
 pub struct ContainerDetails {
     pub AppArmorProfile: String,
@@ -62,7 +67,7 @@ pub struct ContainerDetails {
     pub Config: Config,
     pub Created: String,
     pub Driver: String,
-    // pub ExecIDs: ??
+    pub ExecIDs: Option<Vec<String>>,
     pub HostConfig: HostConfig,
     pub HostnamePath: String,
     pub HostsPath: String,
@@ -70,70 +75,341 @@ pub struct ContainerDetails {
     pub Id: String,
     pub Image: String,
     pub MountLabel: String,
+    pub Name: String,
     pub NetworkSettings: NetworkSettings,
     pub Path: String,
+    pub Platform: Option<String>,
     pub ProcessLabel: String,
     pub ResolvConfPath: String,
     pub RestartCount: u64,
+    /// only reported when inspecting with `size=true`
+    pub SizeRw: Option<i64>,
+    pub SizeRootFs: Option<i64>,
     pub State: State,
     pub Mounts: Vec<Mount>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 #[allow(non_snake_case)]
 pub struct Mount {
+    pub Type: Option<String>,
+    pub Name: Option<String>,
     pub Source: String,
     pub Destination: String,
+    pub Driver: Option<String>,
     pub Mode: String,
     pub RW: bool,
+    pub Propagation: Option<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 #[allow(non_snake_case)]
 pub struct State {
+    pub Status: Option<String>,
     pub Error: String,
     pub ExitCode: u64,
     pub FinishedAt: String,
     pub OOMKilled: bool,
+    pub Dead: Option<bool>,
     pub Paused: bool,
     pub Pid: u64,
     pub Restarting: bool,
     pub Running: bool,
     pub StartedAt: String,
+    /// only reported for containers with a healthcheck
+    pub Health: Option<Health>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct Health {
+    /// `starting`, `healthy` or `unhealthy`
+    pub Status: String,
+    pub FailingStreak: u64,
+    pub Log: Option<Vec<HealthcheckResult>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct HealthcheckResult {
+    pub Start: String,
+    pub End: String,
+    pub ExitCode: i64,
+    pub Output: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 #[allow(non_snake_case)]
 pub struct NetworkSettings {
     pub Bridge: String,
+    pub SandboxID: String,
+    pub SandboxKey: String,
+    pub HairpinMode: bool,
+    pub LinkLocalIPv6Address: String,
+    pub LinkLocalIPv6PrefixLen: u64,
+    /// bindings keyed by `<port>/<protocol>`, null for exposed ports which aren't
+    /// published
+    pub Ports: Option<HashMap<String, Option<Vec<PortBinding>>>>,
+    pub EndpointID: String,
     pub Gateway: String,
+    pub GlobalIPv6Address: String,
+    pub GlobalIPv6PrefixLen: u64,
     pub IPAddress: String,
     pub IPPrefixLen: u64,
-    pub MacAddress: String, /*    pub PortMapping: Option<???>,
-                             *   pub Ports: Option<???> */
+    pub IPv6Gateway: String,
+    pub MacAddress: String,
+    /// endpoints keyed by the name of the network
+    pub Networks: Option<HashMap<String, EndpointSettings>>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl NetworkSettings {
+    /// Host port `port` of the container is published on, the first one if it is
+    /// bound more than once
+    pub fn host_port(&self, port: u16, protocol: &str) -> Option<u16> {
+        let bindings = self.Ports.as_ref()?.get(&format!("{}/{}", port, protocol))?;
+
+        bindings
+            .iter()
+            .flatten()
+            .filter_map(|binding| binding.HostPort.parse().ok())
+            .next()
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct PortBinding {
+    pub HostIp: String,
+    pub HostPort: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct EndpointSettings {
+    pub IPAMConfig: Option<EndpointIPAMConfig>,
+    pub Links: Option<Vec<String>>,
+    pub Aliases: Option<Vec<String>>,
+    pub NetworkID: String,
+    pub EndpointID: String,
+    pub Gateway: String,
+    pub IPAddress: String,
+    pub IPPrefixLen: u64,
+    pub IPv6Gateway: String,
+    pub GlobalIPv6Address: String,
+    pub GlobalIPv6PrefixLen: u64,
+    pub MacAddress: String,
+    pub DriverOpts: Option<HashMap<String, String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct EndpointIPAMConfig {
+    pub IPv4Address: Option<String>,
+    pub IPv6Address: Option<String>,
+    pub LinkLocalIPs: Option<Vec<String>>,
+}
+
+/// Host configuration of a container. Unset fields are reported as zero values, or
+/// null for lists and maps.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 #[allow(non_snake_case)]
 pub struct HostConfig {
-    pub CgroupParent: Option<String>,
+    pub Binds: Option<Vec<String>>,
     pub ContainerIDFile: String,
-    pub CpuShares: Option<u64>,
-    pub CpusetCpus: Option<String>,
-    pub Memory: Option<u64>,
-    pub MemorySwap: Option<u64>,
+    pub LogConfig: Option<LogConfig>,
     pub NetworkMode: String,
-    pub PidMode: Option<String>,
-    // pub PortBindings: ???
+    pub PortBindings: Option<HashMap<String, Option<Vec<PortBinding>>>>,
+    pub RestartPolicy: Option<RestartPolicy>,
+    pub AutoRemove: bool,
+    pub VolumeDriver: String,
+    pub VolumesFrom: Option<Vec<String>>,
+    pub Mounts: Option<Vec<HostMount>>,
+    pub CapAdd: Option<Vec<String>>,
+    pub CapDrop: Option<Vec<String>>,
+    pub CgroupnsMode: Option<String>,
+    pub Dns: Option<Vec<String>>,
+    pub DnsOptions: Option<Vec<String>>,
+    pub DnsSearch: Option<Vec<String>>,
+    pub ExtraHosts: Option<Vec<String>>,
+    pub GroupAdd: Option<Vec<String>>,
+    pub IpcMode: String,
+    pub Cgroup: String,
+    pub Links: Option<Vec<String>>,
+    pub OomScoreAdj: i64,
+    pub PidMode: String,
     pub Privileged: bool,
     pub PublishAllPorts: bool,
-    pub ReadonlyRootfs: Option<bool>, /* pub RestartPolicy: ???
-                                       * pub SecurityOpt: Option<???>,
-                                       * pub Ulimits: Option<???>
-                                       * pub VolumesFrom: Option<??/> */
+    pub ReadonlyRootfs: bool,
+    pub SecurityOpt: Option<Vec<String>>,
+    pub StorageOpt: Option<HashMap<String, String>>,
+    pub Tmpfs: Option<HashMap<String, String>>,
+    pub UTSMode: String,
+    pub UsernsMode: String,
+    pub ShmSize: i64,
+    pub Sysctls: Option<HashMap<String, String>>,
+    pub Runtime: Option<String>,
+    pub Isolation: String,
+    pub Init: Option<bool>,
+    pub MaskedPaths: Option<Vec<String>>,
+    pub ReadonlyPaths: Option<Vec<String>>,
+    pub ConsoleSize: Option<Vec<u64>>,
+
+    // Resources
+    pub CpuShares: i64,
+    pub Memory: i64,
+    pub CgroupParent: String,
+    pub BlkioWeight: u16,
+    pub BlkioWeightDevice: Option<Vec<WeightDevice>>,
+    pub BlkioDeviceReadBps: Option<Vec<ThrottleDevice>>,
+    pub BlkioDeviceWriteBps: Option<Vec<ThrottleDevice>>,
+    pub BlkioDeviceReadIOps: Option<Vec<ThrottleDevice>>,
+    pub BlkioDeviceWriteIOps: Option<Vec<ThrottleDevice>>,
+    pub CpuPeriod: i64,
+    pub CpuQuota: i64,
+    pub CpuRealtimePeriod: i64,
+    pub CpuRealtimeRuntime: i64,
+    pub CpusetCpus: String,
+    pub CpusetMems: String,
+    pub Devices: Option<Vec<DeviceMapping>>,
+    pub DeviceCgroupRules: Option<Vec<String>>,
+    pub DeviceRequests: Option<Vec<DeviceRequest>>,
+    pub MemoryReservation: i64,
+    /// -1 for unlimited swap
+    pub MemorySwap: i64,
+    pub MemorySwappiness: Option<i64>,
+    pub NanoCpus: i64,
+    pub OomKillDisable: Option<bool>,
+    pub PidsLimit: Option<i64>,
+    pub Ulimits: Option<Vec<Ulimit>>,
+    pub CpuCount: i64,
+    pub CpuPercent: i64,
+    pub IOMaximumIOps: u64,
+    pub IOMaximumBandwidth: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct LogConfig {
+    pub Type: String,
+    pub Config: Option<HashMap<String, String>>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct RestartPolicy {
+    /// empty, `no`, `always`, `unless-stopped` or `on-failure`
+    pub Name: String,
+    pub MaximumRetryCount: i64,
+}
+
+/// Mount requested with the `Mounts` of a host configuration
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct HostMount {
+    pub Target: String,
+    pub Source: String,
+    /// `bind`, `volume`, `tmpfs` or `npipe`
+    pub Type: String,
+    pub ReadOnly: bool,
+    pub Consistency: Option<String>,
+    pub BindOptions: Option<BindOptions>,
+    pub VolumeOptions: Option<VolumeOptions>,
+    pub TmpfsOptions: Option<TmpfsOptions>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct BindOptions {
+    pub Propagation: Option<String>,
+    pub NonRecursive: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct VolumeOptions {
+    pub NoCopy: bool,
+    pub Labels: Option<HashMap<String, String>>,
+    pub DriverConfig: Option<VolumeDriverConfig>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct VolumeDriverConfig {
+    pub Name: String,
+    pub Options: Option<HashMap<String, String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct TmpfsOptions {
+    pub SizeBytes: Option<i64>,
+    pub Mode: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct WeightDevice {
+    pub Path: String,
+    pub Weight: u16,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct ThrottleDevice {
+    pub Path: String,
+    pub Rate: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct DeviceMapping {
+    pub PathOnHost: String,
+    pub PathInContainer: String,
+    pub CgroupPermissions: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct DeviceRequest {
+    pub Driver: String,
+    /// -1 for all the devices
+    pub Count: i64,
+    pub DeviceIDs: Option<Vec<String>>,
+    pub Capabilities: Option<Vec<Vec<String>>>,
+    pub Options: Option<HashMap<String, String>>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+#[allow(non_snake_case)]
+pub struct Ulimit {
+    pub Name: String,
+    pub Soft: i64,
+    pub Hard: i64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 #[allow(non_snake_case)]
 pub struct Config {
     pub AttachStderr: bool,
@@ -143,10 +419,11 @@ pub struct Config {
     pub Domainname: String,
     pub Entrypoint: Option<Vec<String>>,
     pub Env: Option<Vec<String>>,
-    // ExposedPorts
+    /// exposed ports keyed by `<port>/<protocol>`, the values are empty objects
+    pub ExposedPorts: Option<HashMap<String, Value>>,
     pub Hostname: String,
     pub Image: String,
-    pub Labels: HashMap<String, String>,
+    pub Labels: Option<HashMap<String, String>>,
     // pub MacAddress: String,
     pub OnBuild: Option<Vec<String>>,
     // pub NetworkDisabled: bool,
@@ -243,6 +520,41 @@ pub struct NetworkCreateInfo {
     pub Warning: String,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct VolumeDetails {
+    pub Name: String,
+    pub Driver: String,
+    pub Mountpoint: String,
+    pub CreatedAt: Option<String>,
+    pub Scope: String,
+    pub Labels: Option<HashMap<String, String>>,
+    pub Options: Option<HashMap<String, String>>,
+    pub Status: Option<HashMap<String, Value>>,
+    pub UsageData: Option<VolumeUsageData>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct VolumeUsageData {
+    pub Size: i64,
+    pub RefCount: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct VolumeList {
+    pub Volumes: Option<Vec<VolumeDetails>>,
+    pub Warnings: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct VolumePruneInfo {
+    pub VolumesDeleted: Option<Vec<String>>,
+    pub SpaceReclaimed: u64,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MemoryStats {
     pub max_usage: u64,
@@ -397,6 +709,55 @@ pub struct Exit {
     pub StatusCode: u64,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct ExecDetails {
+    pub ID: String,
+    pub ContainerID: String,
+    pub Running: bool,
+    pub ExitCode: Option<i64>,
+    pub CanRemove: bool,
+    pub DetachKeys: String,
+    pub OpenStdin: bool,
+    pub OpenStdout: bool,
+    pub OpenStderr: bool,
+    pub Pid: u64,
+    pub ProcessConfig: ExecProcessConfig,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecProcessConfig {
+    pub tty: bool,
+    pub entrypoint: String,
+    pub arguments: Vec<String>,
+    pub privileged: Option<bool>,
+    pub user: Option<String>,
+}
+
+/// Output stream a log line was written to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogStream {
+    StdIn,
+    StdOut,
+    StdErr,
+}
+
+/// A single line of container output
+#[derive(Clone, Debug)]
+pub struct LogLine {
+    pub stream: LogStream,
+    pub timestamp: Option<SystemTime>,
+    pub message: String,
+}
+
+/// Collected output and exit code of a finished exec instance
+#[derive(Clone, Debug, Default)]
+pub struct ExecOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub exit_code: i64,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[allow(non_snake_case)]
 pub struct Event {
@@ -407,6 +768,90 @@ pub struct Event {
     pub timeNano: u64,
 }
 
+/// Progress message streamed while pulling or pushing an image
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct PullProgress {
+    pub id: Option<String>,
+    pub status: Option<String>,
+    pub progress: Option<String>,
+    pub progressDetail: Option<ProgressDetail>,
+    pub error: Option<String>,
+    pub errorDetail: Option<ErrorDetail>,
+}
+
+/// Message streamed while building an image, `stream` carries the output of the
+/// Dockerfile steps
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct BuildOutput {
+    pub stream: Option<String>,
+    pub status: Option<String>,
+    pub id: Option<String>,
+    pub progress: Option<String>,
+    pub progressDetail: Option<ProgressDetail>,
+    pub aux: Option<BuildAux>,
+    pub error: Option<String>,
+    pub errorDetail: Option<ErrorDetail>,
+}
+
+/// Auxiliary build message, reporting the id of the built image
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(non_snake_case)]
+pub struct BuildAux {
+    pub ID: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProgressDetail {
+    pub current: Option<u64>,
+    pub total: Option<u64>,
+}
+
+/// Error reported in the middle of a streamed response
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    pub code: Option<i64>,
+    pub message: Option<String>,
+}
+
+impl PullProgress {
+    /// Digest of the image, reported once a pull (`Digest: sha256:...`) or a push
+    /// (`latest: digest: sha256:... size: 528`) is complete
+    pub fn digest(&self) -> Option<&str> {
+        let status = self.status.as_ref()?;
+        let start = status.find("Digest: ").or_else(|| status.find("digest: "))?;
+
+        status[start + "digest: ".len()..].split_whitespace().next()
+    }
+}
+
+/// Stat of a path in a container, `mode` holds the bits of a Go `os.FileMode`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PathStat {
+    pub name: String,
+    pub size: i64,
+    pub mode: u32,
+    pub mtime: String,
+    #[serde(rename = "linkTarget")]
+    pub link_target: String,
+}
+
+impl PathStat {
+    pub fn is_dir(&self) -> bool {
+        self.mode & (1 << 31) != 0
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.mode & (1 << 27) != 0
+    }
+
+    /// Unix permission bits
+    pub fn permissions(&self) -> u32 {
+        self.mode & 0o777
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Status {
     Untagged(String),
@@ -0,0 +1,130 @@
+//! Request bodies written on a background thread as the daemon consumes them
+
+use futures::stream;
+use futures::sync::mpsc;
+use futures::Future;
+use futures::Sink;
+use futures::Stream;
+use hyper::Body;
+use hyper::Chunk;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::thread;
+
+use errors::Error;
+use errors::ErrorKind;
+use errors::Result;
+
+/// Size of the chunks the body is sent in
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Streams what `produce` writes, running it on its own thread. The writer blocks
+/// while the previous chunk hasn't been sent yet, so the memory used stays flat.
+/// An error returned by `produce` fails the body, aborting the request. Paths should
+/// be checked beforehand as the request has been sent by then.
+pub fn body<F>(produce: F) -> Body
+where
+    F: FnOnce(&mut Write) -> Result<()> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel::<io::Result<Chunk>>(1);
+
+    let spawned = thread::Builder::new()
+        .name("request-body".to_string())
+        .spawn(move || {
+            let mut writer = BufWriter::with_capacity(
+                CHUNK_SIZE,
+                ChunkWriter {
+                    sender: Some(sender),
+                },
+            );
+            let written = produce(&mut writer).and_then(|_| Ok(writer.flush()?));
+
+            if let Err(err) = written {
+                // Io errors are passed as they are to keep their kind
+                let err = match err {
+                    Error(ErrorKind::Io(err), _) => err,
+                    err => io::Error::other(err.to_string()),
+                };
+                if let Some(sender) = writer.get_mut().sender.take() {
+                    let _ = sender.send(Err(err)).wait();
+                }
+            }
+        });
+
+    if let Err(err) = spawned {
+        return Body::wrap_stream(stream::once::<Chunk, _>(Err(err)));
+    }
+
+    let chunks = receiver
+        .map_err(|_| io::Error::other("Request body channel failed"))
+        .and_then(|chunk| chunk);
+
+    Body::wrap_stream(chunks)
+}
+
+/// Writer sending everything written as a chunk of the body
+struct ChunkWriter {
+    sender: Option<mpsc::Sender<io::Result<Chunk>>>,
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let dropped = || io::Error::new(io::ErrorKind::BrokenPipe, "Request body dropped");
+        let sender = self.sender.take().ok_or_else(dropped)?;
+
+        // The receiver is gone once the request is done with the body
+        let sender = sender.send(Ok(Chunk::from(buf.to_vec()))).wait();
+        self.sender = Some(sender.map_err(|_| dropped())?);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::body;
+    use futures::Future;
+    use futures::Stream;
+    use std::io;
+
+    #[test]
+    fn stream_chunks() {
+        let body = body(|writer| {
+            for i in 0..1000u32 {
+                writer.write_all(&[(i % 256) as u8; 1024])?;
+            }
+            Ok(())
+        });
+
+        let chunks = body.collect().wait().expect("Body read failed");
+        assert!(chunks.len() > 1);
+        let bytes = chunks.iter().flat_map(|c| c.iter().cloned()).collect::<Vec<_>>();
+        assert_eq!(bytes.len(), 1000 * 1024);
+        assert_eq!(bytes[1024 * 999], (999 % 256) as u8);
+    }
+
+    #[test]
+    fn stream_error() {
+        let body = body(|writer| {
+            writer.write_all(b"partial")?;
+            Err("Context unreadable".into())
+        });
+
+        body.concat2().wait().expect_err("Body read succeeded");
+    }
+
+    #[test]
+    fn stream_io_error_kind() {
+        let body = body(|_| Err(io::Error::new(io::ErrorKind::NotFound, "Context missing").into()));
+
+        let err = body.concat2().wait().expect_err("Body read succeeded");
+        let cause = err.into_cause().expect("Missing cause");
+        let err = cause.downcast::<io::Error>().expect("Not an io error");
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,310 @@
+//! Exclusion of build context files with the `.dockerignore` pattern semantics
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use errors::Result;
+
+const DOCKERIGNORE: &str = ".dockerignore";
+
+/// Ordered list of exclude patterns, the last pattern matching a path decides whether
+/// it is excluded. Patterns starting with `!` are exceptions re-including paths.
+#[derive(Clone, Debug, Default)]
+pub struct Excludes {
+    patterns: Vec<Pattern>,
+}
+
+impl Excludes {
+    /// Parses the content of a `.dockerignore` file, skipping blank lines and comments
+    pub fn parse(content: &str) -> Excludes {
+        let mut excludes = Excludes::default();
+        for line in content.lines() {
+            let line = line.trim();
+            if !line.starts_with('#') {
+                excludes.add(line);
+            }
+        }
+
+        excludes
+    }
+
+    /// Excludes of a directory build context: the patterns of the ignore file of the
+    /// Dockerfile, `<Dockerfile>.dockerignore`, or else `.dockerignore`, followed by
+    /// `extra`. The Dockerfile and the ignore file are always kept.
+    pub fn for_build_context(
+        context: &Path,
+        dockerfile: &str,
+        extra: &[String],
+    ) -> Result<Excludes> {
+        let dockerfile = clean(dockerfile);
+        let specific = format!("{}{}", dockerfile, DOCKERIGNORE);
+
+        let mut excludes = Excludes::default();
+        let mut ignore_file = None;
+        for name in &[specific.as_str(), DOCKERIGNORE] {
+            match fs::read_to_string(context.join(name)) {
+                Ok(content) => {
+                    excludes = Excludes::parse(&content);
+                    ignore_file = Some(*name);
+                    break;
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        for pattern in extra {
+            excludes.add(pattern);
+        }
+        excludes.add(&format!("!{}", dockerfile));
+        if let Some(name) = ignore_file {
+            excludes.add(&format!("!{}", name));
+        }
+
+        Ok(excludes)
+    }
+
+    /// Appends a pattern, which is an exception when prefixed with `!`
+    pub fn add(&mut self, pattern: &str) {
+        let (exception, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern.trim()),
+            None => (false, pattern),
+        };
+
+        let pattern = clean(pattern);
+        if pattern != "." {
+            self.patterns.push(Pattern::new(pattern, exception));
+        }
+    }
+
+    /// Whether the path, relative to the context root and `/` separated, is excluded
+    pub fn is_excluded(&self, path: &str) -> bool {
+        let parents = match path.rfind('/') {
+            Some(index) => path[..index].split('/').collect(),
+            None => Vec::new(),
+        };
+
+        let mut excluded = false;
+        for pattern in &self.patterns {
+            // Exceptions only matter for paths an earlier pattern excluded
+            if pattern.exception && !excluded {
+                continue;
+            }
+
+            let mut matched = pattern.matches(path);
+            if !matched && pattern.depth <= parents.len() {
+                matched = pattern.matches(&parents[..pattern.depth].join("/"));
+            }
+            if matched {
+                excluded = !pattern.exception;
+            }
+        }
+
+        excluded
+    }
+
+    /// Whether an exception may re-include paths below the excluded directory, which
+    /// then has to be walked
+    pub fn has_exceptions_below(&self, dir: &str) -> bool {
+        let dir = format!("{}/", dir);
+        self.patterns
+            .iter()
+            .any(|p| p.exception && format!("{}/", p.text).starts_with(&dir))
+    }
+}
+
+/// Normalizes a pattern like `filepath.Clean`, without any leading `/`
+fn clean(pattern: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in pattern.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    if segments.is_empty() {
+        ".".to_string()
+    } else {
+        segments.join("/")
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Pattern {
+    text: String,
+    tokens: Vec<Token>,
+    depth: usize,
+    exception: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Char(char),
+    /// `?`, any character but `/`
+    Any,
+    /// `*`, any sequence without `/`
+    Segment,
+    /// `**/`, any number of leading directories, including none
+    Dirs,
+    /// trailing `**`, anything
+    Rest,
+    /// `[...]`, a character class
+    Class(Vec<(char, char)>, bool),
+}
+
+impl Pattern {
+    fn new(text: String, exception: bool) -> Pattern {
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                    }
+                    if chars.peek().is_none() {
+                        Token::Rest
+                    } else {
+                        Token::Dirs
+                    }
+                }
+                '*' => Token::Segment,
+                '?' => Token::Any,
+                '\\' => Token::Char(chars.next().unwrap_or('\\')),
+                '[' => {
+                    let negated = chars.peek() == Some(&'^') || chars.peek() == Some(&'!');
+                    if negated {
+                        chars.next();
+                    }
+
+                    let mut ranges = Vec::new();
+                    while let Some(c) = chars.next() {
+                        if c == ']' {
+                            break;
+                        }
+                        let start = if c == '\\' {
+                            chars.next().unwrap_or(c)
+                        } else {
+                            c
+                        };
+                        if chars.peek() == Some(&'-') {
+                            chars.next();
+                            let end = chars.next().unwrap_or(start);
+                            ranges.push((start, end));
+                        } else {
+                            ranges.push((start, start));
+                        }
+                    }
+                    Token::Class(ranges, negated)
+                }
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+
+        Pattern {
+            depth: text.split('/').count(),
+            text,
+            tokens,
+            exception,
+        }
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let path = path.chars().collect::<Vec<_>>();
+        match_tokens(&self.tokens, &path)
+    }
+}
+
+fn match_tokens(tokens: &[Token], path: &[char]) -> bool {
+    let (token, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => return path.is_empty(),
+    };
+
+    match *token {
+        Token::Char(c) => path.first() == Some(&c) && match_tokens(rest, &path[1..]),
+        Token::Any => match path.first() {
+            Some(&c) if c != '/' => match_tokens(rest, &path[1..]),
+            _ => false,
+        },
+        Token::Class(ref ranges, negated) => match path.first() {
+            Some(&c) if c != '/' => {
+                let within = ranges.iter().any(|&(start, end)| start <= c && c <= end);
+                within != negated && match_tokens(rest, &path[1..])
+            }
+            _ => false,
+        },
+        Token::Segment => {
+            let segment = path.iter().position(|&c| c == '/').unwrap_or(path.len());
+            (0..=segment).any(|skip| match_tokens(rest, &path[skip..]))
+        }
+        Token::Dirs => {
+            match_tokens(rest, path)
+                || path
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &c)| c == '/')
+                    .any(|(index, _)| match_tokens(rest, &path[index + 1..]))
+        }
+        Token::Rest => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Excludes;
+
+    #[test]
+    fn dockerignore_patterns() {
+        let excludes = Excludes::parse(
+            "# build output\n\ntarget\n*.log\n/docs/**/*.md\n!docs/README.md\nnode_modules/\n",
+        );
+
+        assert!(excludes.is_excluded("target"));
+        assert!(excludes.is_excluded("target/debug/app"));
+        assert!(!excludes.is_excluded("src/target"));
+        assert!(excludes.is_excluded("build.log"));
+        assert!(!excludes.is_excluded("logs/build.log"));
+        assert!(excludes.is_excluded("docs/guide.md"));
+        assert!(excludes.is_excluded("docs/api/index.md"));
+        assert!(!excludes.is_excluded("docs/README.md"));
+        assert!(!excludes.is_excluded("docs/logo.png"));
+        assert!(excludes.is_excluded("node_modules/left-pad/index.js"));
+        assert!(!excludes.is_excluded("# build output"));
+    }
+
+    #[test]
+    fn dockerignore_wildcards() {
+        let excludes = Excludes::parse("**/*.tmp\nsrc/?.rs\nimg/[a-c]*.png\n**\n!keep/**");
+
+        assert!(excludes.is_excluded("a.tmp"));
+        assert!(excludes.is_excluded("anything"));
+        assert!(!excludes.is_excluded("keep/data/file"));
+
+        let excludes = Excludes::parse("**/*.tmp\nsrc/?.rs\nimg/[a-c]*.png\n");
+        assert!(excludes.is_excluded("a/b/c.tmp"));
+        assert!(excludes.is_excluded("src/a.rs"));
+        assert!(!excludes.is_excluded("src/ab.rs"));
+        assert!(excludes.is_excluded("img/banner.png"));
+        assert!(!excludes.is_excluded("img/logo.png"));
+    }
+
+    #[test]
+    fn dockerignore_exceptions_below() {
+        let mut excludes = Excludes::parse("vendor\n!vendor/keep/*.go\n");
+        excludes.add("!Dockerfile");
+
+        assert!(excludes.has_exceptions_below("vendor"));
+        assert!(!excludes.has_exceptions_below("target"));
+        assert!(excludes.is_excluded("vendor/drop/a.go"));
+        assert!(!excludes.is_excluded("vendor/keep/a.go"));
+    }
+}
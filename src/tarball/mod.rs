@@ -1 +1,7 @@
+pub mod body;
+pub mod dockerignore;
 pub mod tarball;
+pub mod unpack;
+
+#[cfg(test)]
+mod temp_dir;
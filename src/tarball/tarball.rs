@@ -2,67 +2,389 @@ extern crate flate2;
 extern crate tar;
 
 use self::flate2::write::GzEncoder;
-use self::flate2::Compression;
 
 use self::tar::Archive;
+use self::tar::EntryType;
+use self::tar::Header;
+use build::Compression;
 use std::fs::{self, File};
+use std::io;
+use std::io::Read;
 use std::io::Write;
 use std::path::Path;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 use errors::Result;
+use tarball::dockerignore::Excludes;
+
+/// How the entries of generated archives are written
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Format {
+    pub compression: Compression,
+    /// zero the ownership and modification times, so that identical files give
+    /// byte-identical archives
+    pub reproducible: bool,
+}
 
 // todo: this is pretty involved. (re)factor this into its own crate
-pub fn dir<W>(buf: W, path: &str) -> Result<()>
+pub fn dir<W, P>(buf: W, path: P, format: Format) -> Result<()>
 where
     W: Write,
+    P: AsRef<Path>,
 {
-    let archive = Archive::new(GzEncoder::new(buf, Compression::Best));
+    dir_excluding(buf, path, &Excludes::default(), format)
+}
 
-    let base_path = Path::new(path).canonicalize()?;
-    let mut base_path = base_path.as_path();
+/// Same as `dir`, leaving out the paths under the directory matching `excludes`.
+/// Entries are sorted by path and symlinks are archived as such, never followed.
+pub fn dir_excluding<W, P>(buf: W, path: P, excludes: &Excludes, format: Format) -> Result<()>
+where
+    W: Write,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let archive = Archive::new(Encoder::new(buf, format.compression));
 
-    if base_path.is_file() {
-        // Unwrap can't return None, cause file path cannot be root (`/`)
-        base_path = base_path.parent().expect("File has root filepath!");
+    let append = |entry: &Path, relative: &Path| {
+        append_entry(&archive, entry, relative, format.reproducible)
+    };
+
+    if fs::metadata(path)?.is_dir() {
+        bundle(path, Path::new(""), &append, excludes)?;
+    } else {
+        // A single file is archived under its name
+        let name = path.file_name().ok_or("Archived file path has no name")?;
+        append(path, Path::new(name))?;
     }
 
-    let mut append = |path: &Path| {
-        let canonical = path.canonicalize()?;
-        let relativized = canonical.strip_prefix(base_path)?;
+    archive.finish()?;
+    archive.into_inner().finish()?;
 
-        if path.is_dir() {
-            archive.append_dir(Path::new(relativized), &canonical)?
-        } else {
-            archive.append_file(Path::new(relativized), &mut File::open(&canonical)?)?
-        }
-        Ok(())
+    Ok(())
+}
+
+/// Archives in-memory files given as their path in the archive, mode and content.
+/// The files are dated now, or at the epoch in reproducible archives.
+pub fn files<'a, W, I>(buf: W, files: I, format: Format) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = (&'a str, u32, &'a [u8])>,
+{
+    let archive = Archive::new(Encoder::new(buf, format.compression));
+    let mtime = if format.reproducible {
+        0
+    } else {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0)
     };
 
-    bundle(Path::new(path), &mut append, false)?;
+    for (path, mode, mut content) in files {
+        let mut header = Header::new();
+        header.set_path(path)?;
+        header.set_entry_type(EntryType::file());
+        header.set_mode(mode);
+        header.set_mtime(mtime);
+        header.set_size(content.len() as u64);
+        header.set_cksum();
+
+        archive.append(&header, &mut content)?;
+    }
 
     archive.finish()?;
+    archive.into_inner().finish()?;
 
     Ok(())
 }
 
-fn bundle<F>(dir: &Path, f: &F, bundle_dir: bool) -> Result<()>
+/// Writer compressing the archive, if at all
+enum Encoder<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+}
+
+impl<W: Write> Encoder<W> {
+    fn new(buf: W, compression: Compression) -> Encoder<W> {
+        match compression {
+            Compression::None => Encoder::Plain(buf),
+            Compression::Fast => Encoder::Gzip(GzEncoder::new(buf, flate2::Compression::Fast)),
+            Compression::Best => Encoder::Gzip(GzEncoder::new(buf, flate2::Compression::Best)),
+        }
+    }
+
+    /// Writes the end of the compressed stream, the archive is incomplete without it
+    fn finish(self) -> io::Result<W> {
+        match self {
+            Encoder::Plain(buf) => Ok(buf),
+            Encoder::Gzip(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Encoder::Plain(ref mut writer) => writer.write(buf),
+            Encoder::Gzip(ref mut encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Encoder::Plain(ref mut writer) => writer.flush(),
+            Encoder::Gzip(ref mut encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Relative path with `/` separators, as matched by the exclude patterns
+fn slashed(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn bundle<F>(dir: &Path, relative: &Path, f: &F, excludes: &Excludes) -> Result<()>
 where
-    F: Fn(&Path) -> Result<()>,
+    F: Fn(&Path, &Path) -> Result<()>,
 {
-    if fs::metadata(dir)?.is_dir() {
-        if bundle_dir {
-            f(&dir)?;
-        }
-        for entry in fs::read_dir(dir)? {
-            let entry = entry?;
-            if fs::metadata(entry.path())?.is_dir() {
-                bundle(&entry.path(), f, true)?;
-            } else {
-                f(&entry.path().as_path())?;
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    // The order of read_dir depends on the filesystem
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let relative = relative.join(entry.file_name());
+        let name = slashed(&relative);
+        let is_dir = entry.file_type()?.is_dir();
+
+        if excludes.is_excluded(&name) {
+            // Exceptions can still re-include files below an excluded directory
+            if is_dir && excludes.has_exceptions_below(&name) {
+                bundle(&path, &relative, f, excludes)?;
+            }
+        } else {
+            f(&path, &relative)?;
+            if is_dir {
+                bundle(&path, &relative, f, excludes)?;
             }
         }
+    }
+
+    Ok(())
+}
+
+fn append_entry<W>(
+    archive: &Archive<W>,
+    path: &Path,
+    relative: &Path,
+    reproducible: bool,
+) -> Result<()>
+where
+    W: Write,
+{
+    let meta = fs::symlink_metadata(path)?;
+    let file_type = meta.file_type();
+
+    let mut header = Header::new();
+    header.set_path(relative)?;
+    header.set_metadata(&meta);
+    if reproducible {
+        header.set_uid(0);
+        header.set_gid(0);
+        header.set_mtime(0);
+    }
+
+    if file_type.is_symlink() {
+        header.set_link_name(fs::read_link(path)?)?;
+        header.set_size(0);
+        header.set_cksum();
+        archive.append(&header, &mut io::empty())?;
+    } else if file_type.is_dir() {
+        header.set_cksum();
+        archive.append(&header, &mut io::empty())?;
+    } else if file_type.is_file() {
+        header.set_cksum();
+        let mut content = SizedReader::new(File::open(path)?, meta.len());
+        archive.append(&header, &mut content)?;
     } else {
-        f(&dir)?;
+        debug!("Not archiving {}, which is a special file", path.display());
     }
+
     Ok(())
 }
+
+/// Reader yielding exactly the size written in an entry header. A file growing while
+/// being archived is truncated, one shrinking fails instead of corrupting the archive.
+struct SizedReader<R> {
+    inner: io::Take<R>,
+}
+
+impl<R: Read> SizedReader<R> {
+    fn new(inner: R, size: u64) -> SizedReader<R> {
+        SizedReader {
+            inner: inner.take(size),
+        }
+    }
+}
+
+impl<R: Read> Read for SizedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        if len == 0 && !buf.is_empty() && self.inner.limit() > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "File shrank while being archived",
+            ));
+        }
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dir;
+    use super::dir_excluding;
+    use super::flate2::read::GzDecoder;
+    use super::tar::Archive;
+    use super::Format;
+    use super::SizedReader;
+    use build::Compression;
+    use std::fs;
+    use std::io::Cursor;
+    use std::io::Read;
+    #[cfg(unix)]
+    use std::os::unix::fs::symlink;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    #[cfg(unix)]
+    use std::time::Duration;
+    #[cfg(unix)]
+    use std::time::UNIX_EPOCH;
+    use tarball::dockerignore::Excludes;
+    use tarball::temp_dir::TempDir;
+
+    #[test]
+    fn dir_honours_dockerignore() {
+        let root = TempDir::new("context");
+        for dir in &["src", "target/debug", "docker"] {
+            fs::create_dir_all(root.join(dir)).expect("Directory creation failed");
+        }
+        for file in &["src/main.rs", "target/debug/app", "notes.md", "docker/app.Dockerfile"] {
+            fs::write(root.join(file), "content").expect("File creation failed");
+        }
+        fs::write(root.join(".dockerignore"), "*\n!src\n").expect("File creation failed");
+        fs::write(root.join("docker/app.Dockerfile.dockerignore"), "target\n*.md\n")
+            .expect("File creation failed");
+
+        let entries = |dockerfile: &str| {
+            let excludes =
+                Excludes::for_build_context(&root, dockerfile, &["docker".to_string()])
+                    .expect("Excludes creation failed");
+            let mut bytes = Vec::new();
+            let format = Format {
+                compression: Compression::Best,
+                reproducible: false,
+            };
+            dir_excluding(&mut bytes, &root, &excludes, format).expect("Archive creation failed");
+
+            let mut tar = Vec::new();
+            GzDecoder::new(bytes.as_slice())
+                .and_then(|mut decoder| decoder.read_to_end(&mut tar))
+                .expect("Invalid gzip");
+
+            let archive = Archive::new(Cursor::new(tar));
+            archive
+                .entries()
+                .expect("Invalid archive")
+                .map(|entry| {
+                    let entry = entry.expect("Invalid entry");
+                    entry.header().path().expect("Invalid path").display().to_string()
+                }).collect::<Vec<_>>()
+        };
+
+        assert_eq!(entries("Dockerfile"), vec![".dockerignore", "src", "src/main.rs"]);
+        assert_eq!(
+            entries("docker/app.Dockerfile"),
+            vec![
+                ".dockerignore",
+                "docker/app.Dockerfile",
+                "docker/app.Dockerfile.dockerignore",
+                "src",
+                "src/main.rs",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dir_keeps_symlinks_and_metadata() {
+        let root = TempDir::new("symlinks");
+        fs::create_dir_all(root.join("bin")).expect("Directory creation failed");
+        fs::write(root.join("bin/run.sh"), "#!/bin/sh\n").expect("File creation failed");
+        fs::set_permissions(root.join("bin/run.sh"), fs::Permissions::from_mode(0o755))
+            .expect("Permissions change failed");
+        symlink("bin/run.sh", root.join("run")).expect("Symlink creation failed");
+        symlink("/etc", root.join("etc")).expect("Symlink creation failed");
+
+        let archive = |reproducible: bool| {
+            let mut bytes = Vec::new();
+            let format = Format {
+                compression: Compression::None,
+                reproducible,
+            };
+            dir(&mut bytes, &root, format).expect("Archive creation failed");
+            bytes
+        };
+
+        let tar = archive(true);
+        let entries = Archive::new(Cursor::new(tar.clone()));
+        let headers = entries
+            .entries()
+            .expect("Invalid archive")
+            .map(|entry| {
+                let header = entry.expect("Invalid entry").header().clone();
+                let path = header.path().expect("Invalid path").display().to_string();
+                let link = header
+                    .link_name()
+                    .expect("Invalid link")
+                    .map(|link| link.display().to_string());
+                (path, header.mode().expect("Invalid mode"), link)
+            }).collect::<Vec<_>>();
+
+        assert_eq!(
+            headers,
+            vec![
+                ("bin".to_string(), 0o755, None),
+                ("bin/run.sh".to_string(), 0o755, None),
+                ("etc".to_string(), 0o777, Some("/etc".to_string())),
+                ("run".to_string(), 0o777, Some("bin/run.sh".to_string())),
+            ]
+        );
+
+        // Only the modification times change, which reproducible archives leave out
+        fs::OpenOptions::new()
+            .write(true)
+            .open(root.join("bin/run.sh"))
+            .and_then(|file| file.set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000)))
+            .expect("Modification time change failed");
+        assert_eq!(archive(true), tar);
+        assert_ne!(archive(false), archive(true));
+    }
+
+    #[test]
+    fn sized_reader_matches_header_size() {
+        let mut content = Vec::new();
+        SizedReader::new(Cursor::new("abcdef"), 4)
+            .read_to_end(&mut content)
+            .expect("Read failed");
+        assert_eq!(content, b"abcd");
+
+        let shrunk = SizedReader::new(Cursor::new("ab"), 4).read_to_end(&mut Vec::new());
+        assert!(shrunk.is_err());
+    }
+}
@@ -0,0 +1,45 @@
+//! Scratch directories for the archive tests
+
+use std::env;
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+
+/// Directory below the system temporary directory, removed when dropped so a failed
+/// test doesn't leave it behind
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Empty directory named after `name` and the test process
+    pub fn new(name: &str) -> TempDir {
+        let path = env::temp_dir().join(format!("async_docker_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("Directory creation failed");
+
+        TempDir { path }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,309 @@
+//! Extraction of tar archives streamed by the daemon
+
+extern crate tar;
+
+use self::tar::Archive;
+use errors::ErrorKind;
+use errors::Result;
+use futures::sync::mpsc;
+use futures::sync::oneshot;
+use futures::Future;
+use futures::Sink;
+use futures::Stream;
+use hyper::Chunk;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use Error;
+
+/// Unpacks the archive into `dst` on a separate thread, reading the chunks as they
+/// are received. `dst` is created if missing.
+pub fn unpack_stream<S>(chunks: S, dst: PathBuf) -> impl Future<Item = (), Error = Error> + Send
+where
+    S: Stream<Item = Chunk, Error = Error> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel::<Chunk>(1);
+    let (done, unpacked) = oneshot::channel::<Result<()>>();
+
+    let spawned = thread::Builder::new()
+        .name("archive-unpack".to_string())
+        .spawn(move || {
+            let reader = ChunkReader {
+                chunks: receiver.wait(),
+                current: Chunk::from(Vec::new()),
+                position: 0,
+            };
+            let _ = done.send(unpack(reader, &dst));
+        });
+
+    let forwarded = sender
+        .sink_map_err(|_| None)
+        .send_all(chunks.map_err(Some))
+        .then(|sent| match sent {
+            Ok(_) => Ok(()),
+            Err(Some(err)) => Err(err),
+            // Unpacking stopped reading, its result tells why
+            Err(None) => Ok(()),
+        });
+
+    ::futures::future::result(spawned.map_err(Error::from))
+        .and_then(|_| forwarded)
+        .and_then(|_| {
+            unpacked.then(|unpacked| match unpacked {
+                Ok(unpacked) => unpacked,
+                Err(_) => Err("Archive unpacking thread panicked".into()),
+            })
+        })
+}
+
+/// Unpacks directories, regular files, symlinks and hard links, refusing entries
+/// which would be written outside of `dst`
+pub fn unpack<R>(reader: R, dst: &Path) -> Result<()>
+where
+    R: Read,
+{
+    fs::create_dir_all(dst)?;
+
+    let mut archive = Archive::new(reader);
+    let mut dirs = Vec::new();
+
+    for entry in archive.entries_mut()? {
+        let mut entry = entry?;
+        let header = entry.header().clone();
+        let kind = header.entry_type();
+        let target = destination(dst, &header.path()?)?;
+        let mode = header.mode()?;
+
+        // Never write through an existing symlink
+        if is_symlink(&target) {
+            fs::remove_file(&target)?;
+        }
+
+        if kind.is_dir() {
+            fs::create_dir_all(&target)?;
+            // Applied last, a read-only directory couldn't be filled
+            dirs.push((target, mode));
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if kind.is_file() {
+            let mut file = fs::File::create(&target)?;
+            io::copy(&mut entry, &mut file)?;
+            set_mode(&target, mode)?;
+        } else if kind.is_symlink() {
+            let link = header.link_name()?.ok_or("Symlink entry without a target")?;
+            symlink(&link, &target)?;
+        } else if kind.is_hard_link() {
+            let link = header.link_name()?.ok_or("Hard link entry without a target")?;
+            fs::hard_link(destination(dst, &link)?, &target)?;
+        } else {
+            debug!("Not unpacking {}, which is a special file", target.display());
+        }
+    }
+
+    // A later entry may have replaced a directory with a symlink
+    for (dir, mode) in dirs.into_iter().rev() {
+        if !is_symlink(&dir) {
+            set_mode(&dir, mode)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Path an entry is unpacked to. Entries with absolute paths, parent components or
+/// located below a symlink could be written anywhere and are rejected.
+fn destination(dst: &Path, entry: &Path) -> Result<PathBuf> {
+    let unsafe_entry = || ErrorKind::UnsafeArchiveEntry(entry.display().to_string());
+
+    let mut target = dst.to_path_buf();
+    for component in entry.components() {
+        match component {
+            Component::Normal(name) => {
+                if target != dst && is_symlink(&target) {
+                    return Err(unsafe_entry().into());
+                }
+                target.push(name);
+            }
+            Component::CurDir => {}
+            _ => return Err(unsafe_entry().into()),
+        }
+    }
+
+    Ok(target)
+}
+
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path)
+        .map(|meta| meta.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(link: &Path, path: &Path) -> io::Result<()> {
+    ::std::os::unix::fs::symlink(link, path)
+}
+
+#[cfg(windows)]
+fn symlink(link: &Path, path: &Path) -> io::Result<()> {
+    ::std::os::windows::fs::symlink_file(link, path)
+}
+
+/// Blocking reader over the chunks forwarded to the unpacking thread, ending when
+/// the sender is dropped
+struct ChunkReader<I> {
+    chunks: I,
+    current: Chunk,
+    position: usize,
+}
+
+impl<I> Read for ChunkReader<I>
+where
+    I: Iterator<Item = ::std::result::Result<Chunk, ()>>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.current.len() {
+            match self.chunks.next() {
+                Some(Ok(chunk)) => {
+                    self.current = chunk;
+                    self.position = 0;
+                }
+                _ => return Ok(0),
+            }
+        }
+
+        let remaining = &self.current[self.position..];
+        let read = remaining.len().min(buf.len());
+        buf[..read].copy_from_slice(&remaining[..read]);
+        self.position += read;
+
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unpack;
+    use super::unpack_stream;
+    use super::tar::Archive;
+    use super::tar::EntryType;
+    use super::tar::Header;
+    use errors::ErrorKind;
+    use futures::stream;
+    use futures::Future;
+    use hyper::Chunk;
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    use tarball::temp_dir::TempDir;
+
+    fn archive(entries: &[(&str, EntryType, &str)]) -> Vec<u8> {
+        let archive = Archive::new(Vec::new());
+        for &(path, kind, content) in entries {
+            let mut header = Header::new();
+            header.set_path(path).expect("Invalid path");
+            header.set_entry_type(kind);
+            header.set_mode(0o644);
+            if kind.is_symlink() {
+                header.set_link_name(content).expect("Invalid link");
+                header.set_size(0);
+                header.set_cksum();
+                archive.append(&header, &mut &b""[..]).expect("Append failed");
+            } else {
+                header.set_size(content.len() as u64);
+                header.set_cksum();
+                archive.append(&header, &mut content.as_bytes()).expect("Append failed");
+            }
+        }
+        archive.finish().expect("Archive finish failed");
+        archive.into_inner()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unpack_streamed_archive() {
+        let dst = TempDir::new("unpack");
+
+        let tar = archive(&[
+            ("logs/", EntryType::dir(), ""),
+            ("logs/app.log", EntryType::file(), "started\n"),
+            ("latest", EntryType::symlink(), "logs/app.log"),
+        ]);
+        let chunks = tar
+            .chunks(100)
+            .map(|chunk| Ok(Chunk::from(chunk.to_vec())))
+            .collect::<Vec<_>>();
+
+        unpack_stream(stream::iter_result(chunks), dst.to_path_buf())
+            .wait()
+            .expect("Unpack failed");
+        assert_eq!(
+            fs::read_to_string(dst.join("latest")).expect("Read failed"),
+            "started\n"
+        );
+    }
+
+    #[test]
+    fn unpack_rejects_traversal() {
+        let dst = TempDir::new("traversal");
+
+        let archives = vec![
+            archive(&[("../escaped", EntryType::file(), "x")]),
+            archive(&[("/tmp/escaped", EntryType::file(), "x")]),
+            archive(&[
+                ("etc", EntryType::symlink(), "/etc"),
+                ("etc/escaped", EntryType::file(), "x"),
+            ]),
+        ];
+
+        for tar in archives {
+            let err = unpack(tar.as_slice(), &dst).expect_err("Unpack succeeded");
+            match *err.kind() {
+                ErrorKind::UnsafeArchiveEntry(..) => {}
+                ref kind => panic!("Unexpected error: {:?}", kind),
+            }
+        }
+        assert!(!dst.join("../escaped").exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unpack_replaces_symlinked_directory() {
+        let dst = TempDir::new("symlinked");
+
+        // A directory entry replaces a symlink instead of changing the mode of its target
+        let outside = TempDir::new("outside");
+        fs::set_permissions(&outside, fs::Permissions::from_mode(0o700))
+            .expect("Mode change failed");
+        let link = outside.to_str().expect("Invalid path");
+        let tar = archive(&[
+            ("etc", EntryType::symlink(), link),
+            ("etc/", EntryType::dir(), ""),
+        ]);
+        unpack(tar.as_slice(), &dst).expect("Unpack failed");
+
+        let outside_meta = fs::metadata(&outside).expect("Metadata failed");
+        let etc_meta = fs::symlink_metadata(dst.join("etc")).expect("Metadata failed");
+        assert_eq!(outside_meta.permissions().mode() & 0o777, 0o700);
+        assert!(etc_meta.is_dir());
+    }
+}
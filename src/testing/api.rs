@@ -0,0 +1,658 @@
+//! Engine API endpoints implemented by the fake daemon
+
+use super::state::FakeContainer;
+use super::state::FakeExec;
+use super::state::FakeNetwork;
+use super::state::State;
+use bytes::Bytes;
+use futures::future;
+use futures::stream;
+use futures::Future;
+use futures::Sink;
+use futures::Stream;
+use http::header::CONNECTION;
+use http::header::CONTENT_TYPE;
+use http::header::UPGRADE;
+use hyper::upgrade::OnUpgrade;
+use hyper::upgrade::Upgraded;
+use hyper::Body;
+use hyper::Request;
+use hyper::Response;
+use hyper::StatusCode;
+use serde_json;
+use serde_json::json;
+use serde_json::Value;
+use std::io;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio;
+use tokio::io::AsyncRead;
+use tokio_codec::BytesCodec;
+use tokio_codec::FramedRead;
+use tokio_codec::FramedWrite;
+use url::form_urlencoded;
+
+pub(super) type ResponseFuture = Box<Future<Item = Response<Body>, Error = ::hyper::Error> + Send>;
+
+const RAW_STREAM: &str = "application/vnd.docker.raw-stream";
+
+struct FakeRequest {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl FakeRequest {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.param(name)
+            .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+    }
+
+    fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or(Value::Null)
+    }
+}
+
+pub(super) fn handle(state: Arc<Mutex<State>>, request: Request<Body>) -> ResponseFuture {
+    let (parts, body) = request.into_parts();
+    let path = strip_version(parts.uri.path()).to_string();
+    let query = parts
+        .uri
+        .query()
+        .map(|query| form_urlencoded::parse(query.as_bytes()))
+        .unwrap_or_default();
+
+    // Hijacked endpoints take over the connection instead of reading the body
+    if parts.headers.contains_key(UPGRADE) && parts.method == "POST" {
+        let mut state = state.lock().expect("Fake daemon state lock poisoned");
+        let response = match &segments(&path)[..] {
+            ["containers", id, "attach"] => attach(&mut state, id, body.on_upgrade()),
+            ["exec", id, "start"] => start_exec_upgraded(&mut state, id, body.on_upgrade()),
+            _ => error(StatusCode::NOT_FOUND, "page not found"),
+        };
+
+        return Box::new(future::ok(response));
+    }
+
+    let method = parts.method.as_str().to_string();
+    Box::new(body.concat2().map(move |body| {
+        let request = FakeRequest {
+            method,
+            path,
+            query,
+            body: body.to_vec(),
+        };
+        let mut state = state.lock().expect("Fake daemon state lock poisoned");
+        route(&mut state, &request)
+    }))
+}
+
+fn route(state: &mut State, request: &FakeRequest) -> Response<Body> {
+    match (request.method.as_str(), &segments(&request.path)[..]) {
+        ("GET", ["_ping"]) | ("HEAD", ["_ping"]) => text(StatusCode::OK, "OK"),
+        ("GET", ["version"]) => json(StatusCode::OK, &version()),
+
+        ("GET", ["containers", "json"]) => list_containers(state, request),
+        ("POST", ["containers", "create"]) => create_container(state, request),
+        ("GET", ["containers", id, "json"]) => inspect_container(state, id),
+        ("POST", ["containers", id, "start"]) => start_container(state, id),
+        ("POST", ["containers", id, "stop"]) => stop_container(state, id),
+        ("DELETE", ["containers", id]) => delete_container(state, id, request),
+        ("POST", ["containers", id, "exec"]) => create_exec(state, id, request),
+
+        ("POST", ["exec", id, "start"]) => start_exec(state, id),
+        ("GET", ["exec", id, "json"]) => match state.exec(id) {
+            Some(exec) => json(StatusCode::OK, &exec.details()),
+            None => no_such_exec(id),
+        },
+
+        ("GET", ["images", "json"]) => {
+            let images = state.images.iter().map(|i| i.summary()).collect::<Vec<_>>();
+            json(StatusCode::OK, &Value::Array(images))
+        }
+        ("GET", ["images", name @ .., "json"]) => match state.image(&name.join("/")) {
+            Some(image) => json(StatusCode::OK, &image.details()),
+            None => no_such_image(&name.join("/")),
+        },
+
+        ("GET", ["networks"]) => {
+            let networks = state
+                .networks
+                .iter()
+                .map(|n| n.details(&state.containers))
+                .collect::<Vec<_>>();
+            json(StatusCode::OK, &Value::Array(networks))
+        }
+        ("POST", ["networks", "create"]) => create_network(state, request),
+        ("GET", ["networks", id]) => match state.network_index(id) {
+            Some(index) => json(
+                StatusCode::OK,
+                &state.networks[index].details(&state.containers),
+            ),
+            None => no_such_network(id),
+        },
+        ("DELETE", ["networks", id]) => delete_network(state, id),
+        ("POST", ["networks", id, "connect"]) => connect_network(state, id, request, true),
+        ("POST", ["networks", id, "disconnect"]) => connect_network(state, id, request, false),
+
+        ("GET", ["events"]) => events(state),
+
+        _ => error(StatusCode::NOT_FOUND, "page not found"),
+    }
+}
+
+fn list_containers(state: &State, request: &FakeRequest) -> Response<Body> {
+    let all = request.flag("all");
+    let containers = state
+        .containers
+        .iter()
+        .filter(|c| all || c.running)
+        .map(|c| c.summary(&state.networks))
+        .collect::<Vec<_>>();
+
+    json(StatusCode::OK, &Value::Array(containers))
+}
+
+fn create_container(state: &mut State, request: &FakeRequest) -> Response<Body> {
+    let body = request.json();
+    let reference = match body["Image"].as_str() {
+        Some(reference) => reference.to_string(),
+        None => return error(StatusCode::BAD_REQUEST, "No image was specified"),
+    };
+
+    let id = state.new_id();
+    let name = match request.param("name") {
+        Some(name) => name.trim_start_matches('/').to_string(),
+        None => format!("fake_{}", &id[..8]),
+    };
+
+    if let Some(index) = state.container_index(&format!("/{}", name)) {
+        if state.containers[index].name == name {
+            let message = format!(
+                "Conflict. The container name \"/{}\" is already in use by container \"{}\". \
+                 You have to remove (or rename) that container to be able to reuse that name.",
+                name, state.containers[index].id
+            );
+            return error(StatusCode::CONFLICT, &message);
+        }
+    }
+
+    let container = match state.image(&reference) {
+        Some(image) => FakeContainer::new(id.clone(), name.clone(), image, &reference, &body),
+        None => return no_such_image(&reference),
+    };
+
+    let network = match container.host_config["NetworkMode"].as_str() {
+        Some("default") | None => "bridge".to_string(),
+        Some(mode) => mode.to_string(),
+    };
+    if let Some(index) = state.network_index(&network) {
+        state.networks[index].containers.push(id.clone());
+    }
+
+    state.containers.push(container);
+    state.event(
+        "container",
+        "create",
+        &id,
+        json!({ "image": reference, "name": name }),
+    );
+
+    json(StatusCode::CREATED, &json!({ "Id": id, "Warnings": [] }))
+}
+
+fn inspect_container(state: &mut State, id: &str) -> Response<Body> {
+    match state.container_index(id) {
+        Some(index) => json(
+            StatusCode::OK,
+            &state.containers[index].details(&state.networks),
+        ),
+        None => no_such_container(id),
+    }
+}
+
+fn start_container(state: &mut State, id: &str) -> Response<Body> {
+    let (id, attributes) = match state.container(id) {
+        Some(ref container) if container.running => return empty(StatusCode::NOT_MODIFIED),
+        Some(container) => {
+            container.start();
+            (container.id.clone(), container_attributes(container))
+        }
+        None => return no_such_container(id),
+    };
+
+    state.event("container", "start", &id, attributes);
+    empty(StatusCode::NO_CONTENT)
+}
+
+fn stop_container(state: &mut State, id: &str) -> Response<Body> {
+    let (id, attributes) = match state.container(id) {
+        Some(ref container) if !container.running => return empty(StatusCode::NOT_MODIFIED),
+        Some(container) => {
+            container.stop(0);
+            (container.id.clone(), container_attributes(container))
+        }
+        None => return no_such_container(id),
+    };
+
+    let mut die = attributes.clone();
+    die["exitCode"] = json!("0");
+    state.event("container", "die", &id, die);
+    state.event("container", "stop", &id, attributes);
+    empty(StatusCode::NO_CONTENT)
+}
+
+fn delete_container(state: &mut State, id: &str, request: &FakeRequest) -> Response<Body> {
+    let index = match state.container_index(id) {
+        Some(index) => index,
+        None => return no_such_container(id),
+    };
+
+    if state.containers[index].running {
+        if !request.flag("force") {
+            let message = format!(
+                "You cannot remove a running container {}. Stop the container before \
+                 attempting removal or force remove",
+                state.containers[index].id
+            );
+            return error(StatusCode::CONFLICT, &message);
+        }
+
+        state.containers[index].stop(137);
+        let (id, mut attributes) = (
+            state.containers[index].id.clone(),
+            container_attributes(&state.containers[index]),
+        );
+        attributes["exitCode"] = json!("137");
+        state.event("container", "die", &id, attributes);
+    }
+
+    let container = state.containers.remove(index);
+    for network in &mut state.networks {
+        network.containers.retain(|id| *id != container.id);
+    }
+    state.execs.retain(|exec| exec.container_id != container.id);
+    state.event(
+        "container",
+        "destroy",
+        &container.id,
+        container_attributes(&container),
+    );
+
+    empty(StatusCode::NO_CONTENT)
+}
+
+fn create_exec(state: &mut State, id: &str, request: &FakeRequest) -> Response<Body> {
+    let container_id = match state.container(id) {
+        Some(ref container) if !container.running => {
+            let message = format!("Container {} is not running", container.id);
+            return error(StatusCode::CONFLICT, &message);
+        }
+        Some(container) => container.id.clone(),
+        None => return no_such_container(id),
+    };
+
+    let body = request.json();
+    let cmd = match body["Cmd"].as_array() {
+        Some(cmd) if !cmd.is_empty() => cmd
+            .iter()
+            .filter_map(|arg| arg.as_str().map(String::from))
+            .collect(),
+        _ => return error(StatusCode::BAD_REQUEST, "No exec command specified"),
+    };
+
+    let exec_id = state.new_id();
+    state.execs.push(FakeExec {
+        id: exec_id.clone(),
+        container_id,
+        cmd,
+        tty: body["Tty"].as_bool().unwrap_or(false),
+        running: false,
+        exit_code: None,
+    });
+
+    json(StatusCode::CREATED, &json!({ "Id": exec_id }))
+}
+
+/// Runs the exec instance, returning its multiplexed output or `None` when it doesn't exist
+fn run_exec(state: &mut State, id: &str) -> Option<Vec<Vec<u8>>> {
+    let exec = state.exec(id)?;
+    let (stdout, stderr, exit_code) = exec.run();
+    exec.exit_code = Some(exit_code);
+
+    let chunks = if exec.tty {
+        vec![stdout, stderr]
+    } else {
+        vec![frame(1, &stdout), frame(2, &stderr)]
+    };
+
+    Some(chunks.into_iter().filter(|c| !c.is_empty()).collect())
+}
+
+fn start_exec(state: &mut State, id: &str) -> Response<Body> {
+    match run_exec(state, id) {
+        Some(chunks) => {
+            let chunks = stream::iter_ok::<_, io::Error>(chunks);
+            let mut response = Response::new(Body::wrap_stream(chunks));
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                RAW_STREAM.parse().expect("Constant header value"),
+            );
+            response
+        }
+        None => no_such_exec(id),
+    }
+}
+
+fn start_exec_upgraded(state: &mut State, id: &str, on_upgrade: OnUpgrade) -> Response<Body> {
+    let output = match run_exec(state, id) {
+        Some(chunks) => chunks.concat(),
+        None => return no_such_exec(id),
+    };
+
+    switch_protocols(on_upgrade, move |upgraded| {
+        Box::new(
+            tokio::io::write_all(upgraded, output)
+                .and_then(|(upgraded, _)| tokio::io::shutdown(upgraded))
+                .map(|_| ()),
+        )
+    })
+}
+
+/// Attaches to the container, echoing whatever is written to its stdin back on stdout
+fn attach(state: &mut State, id: &str, on_upgrade: OnUpgrade) -> Response<Body> {
+    let tty = match state.container(id) {
+        Some(container) => container.config["Tty"].as_bool().unwrap_or(false),
+        None => return no_such_container(id),
+    };
+
+    switch_protocols(on_upgrade, move |upgraded| {
+        let (read, write) = upgraded.split();
+        let output = FramedRead::new(read, BytesCodec::new()).map(move |input| {
+            if tty {
+                input.freeze()
+            } else {
+                Bytes::from(frame(1, &input))
+            }
+        });
+
+        Box::new(
+            FramedWrite::new(write, BytesCodec::new())
+                .send_all(output)
+                .map(|_| ()),
+        )
+    })
+}
+
+fn create_network(state: &mut State, request: &FakeRequest) -> Response<Body> {
+    let body = request.json();
+    let name = match body["Name"].as_str() {
+        Some(name) => name.to_string(),
+        None => return error(StatusCode::BAD_REQUEST, "network name is required"),
+    };
+
+    if state.networks.iter().any(|n| n.name == name) {
+        let message = format!("network with name {} already exists", name);
+        return error(StatusCode::CONFLICT, &message);
+    }
+
+    let id = state.new_id();
+    let driver = body["Driver"].as_str().unwrap_or("bridge").to_string();
+    let labels = match body["Labels"] {
+        Value::Object(_) => body["Labels"].clone(),
+        _ => json!({}),
+    };
+
+    state.networks.push(FakeNetwork {
+        id: id.clone(),
+        name: name.clone(),
+        driver: driver.clone(),
+        labels,
+        containers: Vec::new(),
+    });
+    state.event(
+        "network",
+        "create",
+        &id,
+        json!({ "name": name, "type": driver }),
+    );
+
+    json(StatusCode::CREATED, &json!({ "Id": id, "Warning": "" }))
+}
+
+fn delete_network(state: &mut State, id: &str) -> Response<Body> {
+    let index = match state.network_index(id) {
+        Some(index) => index,
+        None => return no_such_network(id),
+    };
+
+    let message = {
+        let network = &state.networks[index];
+        if ["bridge", "host", "none"].contains(&network.name.as_str()) {
+            Some(format!(
+                "{} is a pre-defined network and cannot be removed",
+                network.name
+            ))
+        } else if !network.containers.is_empty() {
+            Some(format!(
+                "error while removing network: network {} id {} has active endpoints",
+                network.name, network.id
+            ))
+        } else {
+            None
+        }
+    };
+
+    if let Some(message) = message {
+        return error(StatusCode::FORBIDDEN, &message);
+    }
+
+    let network = state.networks.remove(index);
+    let attributes = json!({ "name": network.name, "type": network.driver });
+    state.event("network", "destroy", &network.id, attributes);
+
+    empty(StatusCode::NO_CONTENT)
+}
+
+fn connect_network(
+    state: &mut State,
+    id: &str,
+    request: &FakeRequest,
+    connect: bool,
+) -> Response<Body> {
+    let network_index = match state.network_index(id) {
+        Some(index) => index,
+        None => return no_such_network(id),
+    };
+
+    let body = request.json();
+    let reference = body["Container"].as_str().unwrap_or_default();
+    let container_id = match state.container_index(reference) {
+        Some(index) => state.containers[index].id.clone(),
+        None => return no_such_container(reference),
+    };
+
+    let (network_id, attributes) = {
+        let network = &mut state.networks[network_index];
+        let connected = network.containers.contains(&container_id);
+
+        if connect && connected {
+            let message = format!(
+                "endpoint with name {} already exists in network {}",
+                reference, network.name
+            );
+            return error(StatusCode::FORBIDDEN, &message);
+        } else if !connect && !connected {
+            let message = format!(
+                "container {} is not connected to network {}",
+                container_id, network.name
+            );
+            return error(StatusCode::FORBIDDEN, &message);
+        }
+
+        if connect {
+            network.containers.push(container_id.clone());
+        } else {
+            network.containers.retain(|id| *id != container_id);
+        }
+
+        let attributes = json!({
+            "container": container_id,
+            "name": network.name,
+            "type": network.driver,
+        });
+        (network.id.clone(), attributes)
+    };
+
+    let action = if connect { "connect" } else { "disconnect" };
+    state.event("network", action, &network_id, attributes);
+
+    empty(StatusCode::OK)
+}
+
+/// Streams the events recorded so far as JSON lines, one per chunk, then ends the
+/// response instead of waiting for new events
+fn events(state: &State) -> Response<Body> {
+    let lines = state
+        .events
+        .iter()
+        .map(|event| format!("{}\n", event))
+        .collect::<Vec<_>>();
+
+    let mut response = Response::new(Body::wrap_stream(stream::iter_ok::<_, io::Error>(lines)));
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        "application/json".parse().expect("Constant header value"),
+    );
+    response
+}
+
+fn switch_protocols<F>(on_upgrade: OnUpgrade, session: F) -> Response<Body>
+where
+    F: FnOnce(Upgraded) -> Box<Future<Item = (), Error = io::Error> + Send> + Send + 'static,
+{
+    let work = on_upgrade
+        .map_err(|err| warn!("Fake daemon upgrade failed: {}", err))
+        .and_then(move |upgraded| {
+            session(upgraded).map_err(|err| warn!("Fake daemon session failed: {}", err))
+        });
+    tokio::spawn(work);
+
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(CONNECTION, "Upgrade")
+        .header(UPGRADE, "tcp")
+        .header(CONTENT_TYPE, RAW_STREAM)
+        .body(Body::empty())
+        .expect("Fake daemon response building failed")
+}
+
+/// Stream frame of the multiplexed output of containers without a TTY
+fn frame(stream: u8, payload: &[u8]) -> Vec<u8> {
+    if payload.is_empty() {
+        return Vec::new();
+    }
+
+    let mut frame = vec![stream, 0, 0, 0];
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn container_attributes(container: &FakeContainer) -> Value {
+    json!({ "image": container.image, "name": container.name })
+}
+
+fn version() -> Value {
+    json!({
+        "Version": "18.03.1-ce",
+        "ApiVersion": "1.37",
+        "MinAPIVersion": "1.12",
+        "GitCommit": "fake",
+        "GoVersion": "go1.10.1",
+        "Os": "linux",
+        "Arch": "amd64",
+        "KernelVersion": "4.15.0",
+    })
+}
+
+/// Removes the `/v1.xx` prefix of versioned requests
+fn strip_version(path: &str) -> &str {
+    let first = path
+        .trim_start_matches('/')
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    let versioned = first.len() > 1
+        && first.starts_with('v')
+        && first[1..].chars().all(|c| c.is_ascii_digit() || c == '.');
+
+    if versioned {
+        &path[first.len() + 1..]
+    } else {
+        path
+    }
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+fn text(status: StatusCode, body: &'static str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(body))
+        .expect("Fake daemon response building failed")
+}
+
+fn json(status: StatusCode, value: &Value) -> Response<Body> {
+    let body = serde_json::to_vec(value).expect("Fake daemon response serialization failed");
+
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("Fake daemon response building failed")
+}
+
+fn empty(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    json(status, &json!({ "message": message }))
+}
+
+fn no_such_container(id: &str) -> Response<Body> {
+    error(StatusCode::NOT_FOUND, &format!("No such container: {}", id))
+}
+
+fn no_such_image(reference: &str) -> Response<Body> {
+    error(
+        StatusCode::NOT_FOUND,
+        &format!("No such image: {}", reference),
+    )
+}
+
+fn no_such_network(id: &str) -> Response<Body> {
+    error(StatusCode::NOT_FOUND, &format!("network {} not found", id))
+}
+
+fn no_such_exec(id: &str) -> Response<Body> {
+    error(
+        StatusCode::NOT_FOUND,
+        &format!("No such exec instance: {}", id),
+    )
+}
@@ -0,0 +1,392 @@
+//! In-process fake docker daemon, serving a subset of the Engine API over a unix socket
+//!
+//! The daemon keeps its containers, images, networks, exec instances and events in
+//! memory, which makes it possible to exercise the real `UnixDocker` transport, chunked
+//! streaming and hijacked connections without a docker installation.
+//!
+//! ```rust
+//! # extern crate async_docker;
+//! # extern crate tokio;
+//! use async_docker::testing::FakeDaemon;
+//! use async_docker::{new_docker, ContainerOptions};
+//! use tokio::runtime::Runtime;
+//!
+//! # fn main() {
+//! let daemon = FakeDaemon::start().unwrap();
+//! daemon.add_image("alpine");
+//!
+//! let docker = new_docker(Some(daemon.uri())).unwrap();
+//! let opts = ContainerOptions::builder("alpine").build();
+//! let info = Runtime::new()
+//!     .unwrap()
+//!     .block_on(docker.containers().create(&opts))
+//!     .unwrap();
+//! assert_eq!(info.Id.len(), 64);
+//! # }
+//! ```
+//!
+//! Exec instances don't run anything, they only know `echo`, `true` and `false` and
+//! report any other command as not found. Attaching to a container echoes its stdin
+//! back on stdout.
+
+extern crate tokio_uds;
+use self::tokio_uds::UnixListener;
+
+mod api;
+mod state;
+
+use self::state::State;
+use communicate::docker::parse_host;
+use communicate::docker::DockerApi;
+use communicate::new_docker;
+use errors::Result;
+use futures::sync::oneshot;
+use futures::Future;
+use hyper::service::service_fn;
+use hyper::Server;
+use hyper::Uri;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::thread::JoinHandle;
+use tokio::runtime::Runtime;
+
+static SOCKETS: AtomicUsize = AtomicUsize::new(0);
+
+/// Fake docker daemon listening on a temporary unix socket, running on its own thread
+/// until dropped
+pub struct FakeDaemon {
+    path: PathBuf,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FakeDaemon {
+    /// Binds a new socket in the temporary directory and starts serving requests on it
+    pub fn start() -> Result<FakeDaemon> {
+        let name = format!(
+            "async_docker_{}_{}.sock",
+            process::id(),
+            SOCKETS.fetch_add(1, Ordering::SeqCst)
+        );
+        let path = env::temp_dir().join(name);
+        let _ = fs::remove_file(&path);
+
+        let listener = UnixListener::bind(&path)?;
+        let state = Arc::new(Mutex::new(State::new()));
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+
+        let server_state = state.clone();
+        let thread = thread::Builder::new()
+            .name("fake-docker-daemon".to_string())
+            .spawn(move || {
+                let server = Server::builder(listener.incoming())
+                    .serve(move || {
+                        let state = server_state.clone();
+                        service_fn(move |request| api::handle(state.clone(), request))
+                    })
+                    .map_err(|err| warn!("Fake daemon failed: {}", err));
+
+                let mut runtime = Runtime::new().expect("Fake daemon runtime creation failed");
+                let _ = runtime.block_on(server.select(shutdown_signal.map_err(|_| ())));
+            })?;
+
+        Ok(FakeDaemon {
+            path,
+            state,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    /// Path of the socket the daemon listens on
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Host to pass to `new_docker` or `DockerBuilder::host`
+    pub fn uri(&self) -> Uri {
+        parse_host(&format!("unix://{}", self.path.display()))
+            .expect("Fake daemon socket path isn't a valid host")
+    }
+
+    /// Creates a client connected to the daemon
+    pub fn docker(&self) -> Result<Box<DockerApi>> {
+        new_docker(Some(self.uri()))
+    }
+
+    /// Makes an image available to create containers from, returning its id.
+    /// References without a tag are tagged `latest`.
+    pub fn add_image(&self, reference: &str) -> String {
+        self.state
+            .lock()
+            .expect("Fake daemon state lock poisoned")
+            .add_image(reference)
+    }
+}
+
+impl Drop for FakeDaemon {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FakeDaemon;
+    use futures::Future;
+    use futures::Stream;
+    use std::borrow::Cow;
+    use tokio;
+    use tokio::runtime::Runtime;
+    use AttachOptions;
+    use ContainerConnectionOptions;
+    use ContainerListOptions;
+    use ContainerOptions;
+    use ErrorKind;
+    use EventsOptions;
+    use ExecContainerOptions;
+    use ImageListOptions;
+    use NetworkCreateOptions;
+    use RmContainerOptions;
+
+    #[test]
+    fn container_lifecycle() {
+        let daemon = FakeDaemon::start().expect("Fake daemon start failed");
+        daemon.add_image("alpine");
+        let docker = daemon.docker().expect("Docker creation failed");
+        let mut runtime = Runtime::new().expect("Runtime creation failed");
+
+        let opts = ContainerOptions::builder("alpine")
+            .name("web")
+            .cmd(vec!["sleep", "60"])
+            .publish(80, "tcp", "", None)
+            .publish(443, "tcp", "127.0.0.1", Some(8443))
+            .build();
+        let info = runtime
+            .block_on(docker.containers().create(&opts))
+            .expect("Create failed");
+        let conflict = runtime
+            .block_on(docker.containers().create(&opts))
+            .expect_err("Duplicate name accepted");
+        match *conflict.kind() {
+            ErrorKind::Conflict(..) => {}
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+
+        let container = docker.container(Cow::from(info.Id.clone()));
+        runtime.block_on(container.start()).expect("Start failed");
+        match *runtime
+            .block_on(container.start())
+            .expect_err("Restarted")
+            .kind()
+        {
+            ErrorKind::NotModified(..) => {}
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+
+        let host_port = runtime
+            .block_on(container.host_port(80, "tcp"))
+            .expect("Inspect failed");
+        assert!(host_port.expect("Port not published") >= 32768);
+        let host_port = runtime
+            .block_on(container.host_port(443, "tcp"))
+            .expect("Inspect failed");
+        assert_eq!(host_port, Some(8443));
+
+        let details = runtime
+            .block_on(docker.container(Cow::from("web")).inspect())
+            .expect("Inspect failed");
+        assert_eq!(details.Id, info.Id);
+        assert_eq!(details.Path, "sleep");
+        assert_eq!(details.Args, vec!["60"]);
+        assert!(details.State.Running);
+
+        let listed = runtime
+            .block_on(docker.containers().list(&ContainerListOptions::default()))
+            .expect("List failed");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].Names, vec!["/web"]);
+
+        match *runtime
+            .block_on(container.delete())
+            .expect_err("Deleted")
+            .kind()
+        {
+            ErrorKind::Conflict(..) => {}
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+
+        runtime.block_on(container.stop(None)).expect("Stop failed");
+        let listed = runtime
+            .block_on(docker.containers().list(&ContainerListOptions::default()))
+            .expect("List failed");
+        assert!(listed.is_empty());
+
+        let opts = RmContainerOptions::builder().force(true).build();
+        runtime
+            .block_on(container.remove(&opts))
+            .expect("Remove failed");
+        match *runtime
+            .block_on(container.inspect())
+            .expect_err("Inspected")
+            .kind()
+        {
+            ErrorKind::NotFound(_, ref message) => {
+                assert_eq!(*message, format!("No such container: {}", info.Id))
+            }
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+
+        let statuses = runtime
+            .block_on(docker.events(&EventsOptions::default()).collect())
+            .expect("Events failed")
+            .into_iter()
+            .map(|event| event.expect("Invalid event").status.unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(statuses, vec!["create", "start", "die", "stop", "destroy"]);
+    }
+
+    #[test]
+    fn exec_and_attach() {
+        let daemon = FakeDaemon::start().expect("Fake daemon start failed");
+        daemon.add_image("alpine:3.8");
+        let docker = daemon.docker().expect("Docker creation failed");
+        let mut runtime = Runtime::new().expect("Runtime creation failed");
+
+        let opts = ContainerOptions::builder("alpine:3.8").build();
+        let info = runtime
+            .block_on(docker.containers().create(&opts))
+            .expect("Create failed");
+        let container = docker.container(Cow::from(info.Id));
+        runtime.block_on(container.start()).expect("Start failed");
+
+        let opts = ExecContainerOptions::builder()
+            .cmd(vec!["echo", "hello", "world"])
+            .attach_stdout(true)
+            .attach_stderr(true)
+            .build();
+        let output = runtime
+            .block_on(container.exec_output(&opts))
+            .expect("Exec failed");
+        assert_eq!(output.stdout, b"hello world\n");
+        assert_eq!(output.exit_code, 0);
+
+        let opts = ExecContainerOptions::builder()
+            .cmd(vec!["ls"])
+            .attach_stdout(true)
+            .attach_stderr(true)
+            .build();
+        let output = runtime
+            .block_on(container.exec_output(&opts))
+            .expect("Exec failed");
+        assert_eq!(output.stderr, b"ls: command not found\n");
+        assert_eq!(output.exit_code, 127);
+
+        let opts = ExecContainerOptions::builder()
+            .cmd(vec!["echo", "attached"])
+            .attach_stdout(true)
+            .build();
+        let (_, output) = runtime
+            .block_on(container.exec_attached(&opts))
+            .expect("Exec attach failed");
+        let frames = runtime
+            .block_on(output.collect())
+            .expect("Exec output failed");
+        assert_eq!(frames.len(), 1);
+        assert_eq!((frames[0].0, frames[0].1.as_ref()), (1, &b"attached\n"[..]));
+
+        let opts = AttachOptions::builder()
+            .stream(true)
+            .stdin(true)
+            .stdout(true)
+            .build();
+        let (stdin, output) = runtime
+            .block_on(container.attach(&opts))
+            .expect("Attach failed");
+        runtime
+            .block_on(tokio::io::write_all(stdin, b"ping\n"))
+            .expect("Stdin write failed");
+        let (frame, _) = runtime
+            .block_on(output.into_future().map_err(|(err, _)| err))
+            .expect("Attach output failed");
+        let (stream, chunk) = frame.expect("Attach output ended");
+        assert_eq!((stream, chunk.as_ref()), (1, &b"ping\n"[..]));
+    }
+
+    #[test]
+    fn images_and_networks() {
+        let daemon = FakeDaemon::start().expect("Fake daemon start failed");
+        let id = daemon.add_image("library/busybox");
+        let docker = daemon.docker().expect("Docker creation failed");
+        let mut runtime = Runtime::new().expect("Runtime creation failed");
+
+        let images = runtime
+            .block_on(docker.images().list(&ImageListOptions::default()))
+            .expect("Image list failed");
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].RepoTags, vec!["library/busybox:latest"]);
+
+        let image = runtime
+            .block_on(docker.image(Cow::from("library/busybox")).inspect())
+            .expect("Image inspect failed");
+        assert_eq!(image.Id, id);
+
+        let opts = ContainerOptions::builder("library/busybox").build();
+        let container = runtime
+            .block_on(docker.containers().create(&opts))
+            .expect("Create failed")
+            .Id;
+
+        let opts = NetworkCreateOptions::builder("backend").build();
+        let network_id = runtime
+            .block_on(docker.networks().create(&opts))
+            .expect("Network create failed")
+            .Id;
+        let network = docker.network(Cow::from("backend"));
+
+        let opts = ContainerConnectionOptions::new(&container);
+        runtime
+            .block_on(network.connect(&opts))
+            .expect("Connect failed");
+        let details = runtime
+            .block_on(network.inspect())
+            .expect("Network inspect failed");
+        assert_eq!(details.Id, network_id);
+        assert!(details.Containers.contains_key(&container));
+
+        match *runtime
+            .block_on(network.delete())
+            .expect_err("Deleted")
+            .kind()
+        {
+            ErrorKind::UnexpectedStatus(status, _) => assert_eq!(status.as_u16(), 403),
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+
+        runtime
+            .block_on(network.disconnect(&opts))
+            .expect("Disconnect failed");
+        runtime
+            .block_on(network.delete())
+            .expect("Network delete failed");
+        let networks = runtime
+            .block_on(docker.networks().list(&Default::default()))
+            .expect("Network list failed");
+        assert_eq!(networks.len(), 3);
+    }
+}
@@ -0,0 +1,594 @@
+//! In-memory objects managed by the fake daemon
+
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+/// Timestamp reported for every object, the fake daemon has no notion of time
+pub(super) const CREATED: &str = "2018-01-01T00:00:00.000000000Z";
+const ZERO_TIME: &str = "0001-01-01T00:00:00Z";
+
+pub(super) struct State {
+    counter: u64,
+    pub containers: Vec<FakeContainer>,
+    pub images: Vec<FakeImage>,
+    pub networks: Vec<FakeNetwork>,
+    pub execs: Vec<FakeExec>,
+    pub events: Vec<Value>,
+}
+
+pub(super) struct FakeContainer {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    pub image_id: String,
+    pub config: Value,
+    pub host_config: Value,
+    pub running: bool,
+    pub started: bool,
+    pub exit_code: i64,
+    pub started_at: String,
+    pub finished_at: String,
+}
+
+pub(super) struct FakeImage {
+    pub id: String,
+    pub tags: Vec<String>,
+}
+
+pub(super) struct FakeNetwork {
+    pub id: String,
+    pub name: String,
+    pub driver: String,
+    pub labels: Value,
+    pub containers: Vec<String>,
+}
+
+pub(super) struct FakeExec {
+    pub id: String,
+    pub container_id: String,
+    pub cmd: Vec<String>,
+    pub tty: bool,
+    pub running: bool,
+    pub exit_code: Option<i64>,
+}
+
+impl State {
+    pub fn new() -> State {
+        let mut state = State {
+            counter: 0,
+            containers: Vec::new(),
+            images: Vec::new(),
+            networks: Vec::new(),
+            execs: Vec::new(),
+            events: Vec::new(),
+        };
+
+        for &(name, driver) in &[("bridge", "bridge"), ("host", "host"), ("none", "null")] {
+            let id = state.new_id();
+            state.networks.push(FakeNetwork {
+                id,
+                name: name.to_string(),
+                driver: driver.to_string(),
+                labels: json!({}),
+                containers: Vec::new(),
+            });
+        }
+
+        state
+    }
+
+    /// Generates a 64 hex digits id, unique within the daemon
+    pub fn new_id(&mut self) -> String {
+        self.counter += 1;
+
+        // splitmix64, so that ids don't share their prefixes
+        let mut seed = self.counter;
+        (0..4)
+            .map(|_| {
+                seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                let mut z = seed;
+                z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                format!("{:016x}", z ^ (z >> 31))
+            })
+            .collect()
+    }
+
+    pub fn add_image(&mut self, reference: &str) -> String {
+        let tag = normalize_tag(reference);
+        if let Some(image) = self.image(&tag) {
+            return image.id.clone();
+        }
+
+        let id = format!("sha256:{}", self.new_id());
+        self.images.push(FakeImage {
+            id: id.clone(),
+            tags: vec![tag],
+        });
+        id
+    }
+
+    pub fn image(&self, reference: &str) -> Option<&FakeImage> {
+        let tag = normalize_tag(reference);
+        self.images.iter().find(|image| {
+            image.tags.contains(&tag)
+                || image.id == reference
+                || image.id[7..].starts_with(reference.trim_start_matches("sha256:"))
+        })
+    }
+
+    pub fn container_index(&self, reference: &str) -> Option<usize> {
+        let name = reference.trim_start_matches('/');
+        self.containers
+            .iter()
+            .position(|c| c.id == reference || c.name == name)
+            .or_else(|| {
+                self.containers
+                    .iter()
+                    .position(|c| !reference.is_empty() && c.id.starts_with(reference))
+            })
+    }
+
+    pub fn container(&mut self, reference: &str) -> Option<&mut FakeContainer> {
+        match self.container_index(reference) {
+            Some(index) => Some(&mut self.containers[index]),
+            None => None,
+        }
+    }
+
+    pub fn network_index(&self, reference: &str) -> Option<usize> {
+        self.networks
+            .iter()
+            .position(|n| n.id == reference || n.name == reference)
+            .or_else(|| {
+                self.networks
+                    .iter()
+                    .position(|n| !reference.is_empty() && n.id.starts_with(reference))
+            })
+    }
+
+    pub fn exec(&mut self, id: &str) -> Option<&mut FakeExec> {
+        self.execs.iter_mut().find(|exec| exec.id == id)
+    }
+
+    /// Records an event reported by the events endpoint
+    pub fn event(&mut self, kind: &str, action: &str, id: &str, attributes: Value) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let nanos = now.as_secs() * 1_000_000_000 + u64::from(now.subsec_nanos());
+
+        let mut event = json!({
+            "Type": kind,
+            "Action": action,
+            "Actor": { "ID": id, "Attributes": attributes },
+            "scope": "local",
+            "time": now.as_secs(),
+            "timeNano": nanos,
+        });
+
+        if kind == "container" {
+            event["status"] = json!(action);
+            event["id"] = json!(id);
+            event["from"] = event["Actor"]["Attributes"]["image"].clone();
+        }
+
+        self.events.push(event);
+    }
+}
+
+impl FakeContainer {
+    pub fn new(
+        id: String,
+        name: String,
+        image: &FakeImage,
+        reference: &str,
+        body: &Value,
+    ) -> FakeContainer {
+        let mut config = json!({
+            "Hostname": id[..12],
+            "Domainname": "",
+            "User": "",
+            "AttachStdin": false,
+            "AttachStdout": true,
+            "AttachStderr": true,
+            "Tty": false,
+            "OpenStdin": false,
+            "StdinOnce": false,
+            "Env": null,
+            "Cmd": null,
+            "Image": reference,
+            "Volumes": null,
+            "WorkingDir": "",
+            "Entrypoint": null,
+            "OnBuild": null,
+            "Labels": {},
+        });
+        let mut host_config = json!({
+            "CgroupParent": "",
+            "ContainerIDFile": "",
+            "CpuShares": 0,
+            "CpusetCpus": "",
+            "Memory": 0,
+            "MemorySwap": 0,
+            "NetworkMode": "default",
+            "PidMode": "",
+            "Privileged": false,
+            "PublishAllPorts": false,
+            "ReadonlyRootfs": false,
+        });
+
+        if let Some(fields) = body.as_object() {
+            for (key, value) in fields {
+                match key.as_str() {
+                    "HostConfig" => merge(&mut host_config, value),
+                    "NetworkingConfig" => {}
+                    _ => {
+                        config[key.as_str()] = value.clone();
+                    }
+                }
+            }
+        }
+
+        FakeContainer {
+            id,
+            name,
+            image: reference.to_string(),
+            image_id: image.id.clone(),
+            config,
+            host_config,
+            running: false,
+            started: false,
+            exit_code: 0,
+            started_at: ZERO_TIME.to_string(),
+            finished_at: ZERO_TIME.to_string(),
+        }
+    }
+
+    pub fn status(&self) -> &'static str {
+        match (self.running, self.started) {
+            (true, _) => "running",
+            (false, true) => "exited",
+            (false, false) => "created",
+        }
+    }
+
+    fn cmd(&self) -> Vec<String> {
+        let strings = |value: &Value| -> Vec<String> {
+            value
+                .as_array()
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut cmd = strings(&self.config["Entrypoint"]);
+        cmd.extend(strings(&self.config["Cmd"]));
+        cmd
+    }
+
+    fn ip_address(&self) -> String {
+        if self.running {
+            // The low bytes of the id stand in for an address allocator
+            let host = u8::from_str_radix(&self.id[..2], 16).unwrap_or(0) % 250;
+            format!("172.17.0.{}", host + 2)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Representation returned by the list endpoint
+    pub fn summary(&self, networks: &[FakeNetwork]) -> Value {
+        let status = match self.status() {
+            "running" => "Up Less than a second".to_string(),
+            "exited" => format!("Exited ({}) Less than a second ago", self.exit_code),
+            _ => "Created".to_string(),
+        };
+
+        json!({
+            "Id": self.id,
+            "Names": [format!("/{}", self.name)],
+            "Image": self.image,
+            "ImageID": self.image_id,
+            "Command": self.cmd().join(" "),
+            "Created": 1_514_764_800u64,
+            "Ports": [],
+            "Labels": labels(&self.config),
+            "State": self.status(),
+            "Status": status,
+            "HostConfig": { "NetworkMode": self.host_config["NetworkMode"] },
+            "NetworkSettings": { "Networks": self.networks(networks) },
+            "Mounts": [],
+        })
+    }
+
+    /// Representation returned by the inspect endpoint
+    pub fn details(&self, networks: &[FakeNetwork]) -> Value {
+        let cmd = self.cmd();
+        let running = self.running;
+        let ip_address = self.ip_address();
+
+        json!({
+            "Id": self.id,
+            "Created": CREATED,
+            "Path": cmd.first().cloned().unwrap_or_default(),
+            "Args": cmd.iter().skip(1).collect::<Vec<_>>(),
+            "State": {
+                "Status": self.status(),
+                "Running": running,
+                "Paused": false,
+                "Restarting": false,
+                "OOMKilled": false,
+                "Dead": false,
+                "Pid": if running { 4242 } else { 0 },
+                "ExitCode": self.exit_code,
+                "Error": "",
+                "StartedAt": self.started_at,
+                "FinishedAt": self.finished_at,
+            },
+            "Image": self.image_id,
+            "ResolvConfPath": "",
+            "HostnamePath": "",
+            "HostsPath": "",
+            "LogPath": "",
+            "Name": format!("/{}", self.name),
+            "RestartCount": 0,
+            "Driver": "overlay2",
+            "MountLabel": "",
+            "ProcessLabel": "",
+            "AppArmorProfile": "",
+            "HostConfig": self.host_config,
+            "Mounts": [],
+            "Config": self.config,
+            "NetworkSettings": {
+                "Bridge": "",
+                "Gateway": if running { "172.17.0.1" } else { "" },
+                "IPAddress": ip_address,
+                "IPPrefixLen": if running { 16 } else { 0 },
+                "MacAddress": "",
+                "Ports": self.ports(),
+                "Networks": self.networks(networks),
+            },
+        })
+    }
+
+    /// Exposed ports and, while running, their bindings. Ephemeral host ports are
+    /// derived from the id so that they are stable.
+    fn ports(&self) -> Value {
+        let mut ports = Map::new();
+        if let Some(exposed) = self.config["ExposedPorts"].as_object() {
+            for port in exposed.keys() {
+                ports.insert(port.clone(), Value::Null);
+            }
+        }
+        if !self.running {
+            return Value::Object(ports);
+        }
+
+        let seed = u16::from_str_radix(&self.id[..4], 16).unwrap_or(0);
+        if let Some(published) = self.host_config["PortBindings"].as_object() {
+            for (port, bindings) in published {
+                let number = port.split('/').next().and_then(|p| p.parse::<u16>().ok());
+                let ephemeral = 32768 + (seed ^ number.unwrap_or(0)) % 28000;
+
+                let bindings = bindings
+                    .as_array()
+                    .map(|bindings| bindings.as_slice())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|binding| {
+                        let host_ip = match binding["HostIp"].as_str() {
+                            Some(ip) if !ip.is_empty() => ip,
+                            _ => "0.0.0.0",
+                        };
+                        let host_port = match binding["HostPort"].as_str() {
+                            Some(port) if !port.is_empty() => port.to_string(),
+                            _ => ephemeral.to_string(),
+                        };
+                        json!({ "HostIp": host_ip, "HostPort": host_port })
+                    })
+                    .collect::<Vec<_>>();
+                ports.insert(port.clone(), Value::Array(bindings));
+            }
+        }
+        Value::Object(ports)
+    }
+
+    fn networks(&self, networks: &[FakeNetwork]) -> Value {
+        let mut attached = Map::new();
+        for network in networks.iter().filter(|n| n.containers.contains(&self.id)) {
+            attached.insert(
+                network.name.clone(),
+                json!({
+                    "NetworkID": network.id,
+                    "EndpointID": if self.running { self.id.as_str() } else { "" },
+                    "IPAddress": self.ip_address(),
+                    "MacAddress": "",
+                }),
+            );
+        }
+        Value::Object(attached)
+    }
+
+    pub fn start(&mut self) {
+        self.running = true;
+        self.started = true;
+        self.exit_code = 0;
+        self.started_at = CREATED.to_string();
+        self.finished_at = ZERO_TIME.to_string();
+    }
+
+    pub fn stop(&mut self, exit_code: i64) {
+        self.running = false;
+        self.exit_code = exit_code;
+        self.finished_at = CREATED.to_string();
+    }
+}
+
+impl FakeImage {
+    pub fn summary(&self) -> Value {
+        json!({
+            "Id": self.id,
+            "ParentId": "",
+            "RepoTags": self.tags,
+            "RepoDigests": [],
+            "Created": 1_514_764_800u64,
+            "Size": 0,
+            "SharedSize": -1,
+            "VirtualSize": 0,
+            "Labels": null,
+            "Containers": -1,
+        })
+    }
+
+    pub fn details(&self) -> Value {
+        json!({
+            "Id": self.id,
+            "RepoTags": self.tags,
+            "RepoDigests": [],
+            "Parent": "",
+            "Comment": "",
+            "Created": CREATED,
+            "DockerVersion": "",
+            "Author": "",
+            "Config": {
+                "Hostname": "",
+                "Domainname": "",
+                "User": "",
+                "AttachStdin": false,
+                "AttachStdout": false,
+                "AttachStderr": false,
+                "Tty": false,
+                "OpenStdin": false,
+                "StdinOnce": false,
+                "Env": ["PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin"],
+                "Cmd": ["/bin/sh"],
+                "Image": "",
+                "WorkingDir": "",
+                "Entrypoint": null,
+                "OnBuild": null,
+                "Labels": {},
+            },
+            "Architecture": "amd64",
+            "Os": "linux",
+            "Size": 0,
+            "VirtualSize": 0,
+        })
+    }
+}
+
+impl FakeNetwork {
+    pub fn details(&self, containers: &[FakeContainer]) -> Value {
+        let mut attached = Map::new();
+        for container in containers
+            .iter()
+            .filter(|c| self.containers.contains(&c.id))
+        {
+            attached.insert(
+                container.id.clone(),
+                json!({
+                    "Name": container.name,
+                    "EndpointID": container.id,
+                    "MacAddress": "",
+                    "IPv4Address": "",
+                    "IPv6Address": "",
+                }),
+            );
+        }
+
+        json!({
+            "Name": self.name,
+            "Id": self.id,
+            "Created": CREATED,
+            "Scope": "local",
+            "Driver": self.driver,
+            "EnableIPv6": false,
+            "IPAM": { "Driver": "default", "Options": null, "Config": [] },
+            "Internal": false,
+            "Attachable": false,
+            "Ingress": false,
+            "Containers": attached,
+            "Options": {},
+            "Labels": self.labels,
+        })
+    }
+}
+
+impl FakeExec {
+    pub fn details(&self) -> Value {
+        json!({
+            "ID": self.id,
+            "ContainerID": self.container_id,
+            "Running": self.running,
+            "ExitCode": self.exit_code,
+            "CanRemove": false,
+            "DetachKeys": "",
+            "OpenStdin": false,
+            "OpenStdout": true,
+            "OpenStderr": true,
+            "Pid": 0,
+            "ProcessConfig": {
+                "tty": self.tty,
+                "entrypoint": self.cmd.first().cloned().unwrap_or_default(),
+                "arguments": self.cmd.iter().skip(1).collect::<Vec<_>>(),
+                "privileged": false,
+                "user": "",
+            },
+        })
+    }
+
+    /// Runs the command, the fake daemon only knows a few shell builtins and reports
+    /// `(stdout, stderr, exit code)`
+    pub fn run(&self) -> (Vec<u8>, Vec<u8>, i64) {
+        let args = self
+            .cmd
+            .iter()
+            .skip(1)
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        match self.cmd.first().map(String::as_str) {
+            Some("echo") => (format!("{}\n", args.join(" ")).into_bytes(), Vec::new(), 0),
+            Some("true") => (Vec::new(), Vec::new(), 0),
+            Some("false") => (Vec::new(), Vec::new(), 1),
+            Some(command) => (
+                Vec::new(),
+                format!("{}: command not found\n", command).into_bytes(),
+                127,
+            ),
+            None => (Vec::new(), b"no command specified\n".to_vec(), 126),
+        }
+    }
+}
+
+/// Adds the `latest` tag to references without one
+pub(super) fn normalize_tag(reference: &str) -> String {
+    let name = reference.rsplit('/').next().unwrap_or(reference);
+    if name.contains(':') || reference.contains('@') {
+        reference.to_string()
+    } else {
+        format!("{}:latest", reference)
+    }
+}
+
+fn merge(target: &mut Value, source: &Value) {
+    if let Some(fields) = source.as_object() {
+        for (key, value) in fields {
+            target[key.as_str()] = value.clone();
+        }
+    }
+}
+
+fn labels(config: &Value) -> Value {
+    match config["Labels"] {
+        Value::Object(_) => config["Labels"].clone(),
+        _ => json!({}),
+    }
+}
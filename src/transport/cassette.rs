@@ -0,0 +1,368 @@
+//! Recording of the traffic with a docker daemon into cassettes replayed offline
+
+use communicate::util::RequestArgs;
+use communicate::version::ApiVersion;
+use errors::Result;
+use futures::Async;
+use futures::Future;
+use futures::Poll;
+use futures::Stream;
+use http::header::HeaderName;
+use http::header::HeaderValue;
+use hyper::Body;
+use hyper::Chunk;
+use hyper::Method;
+use hyper::Response;
+use hyper::StatusCode;
+use serde_json;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use transport::interact::InteractApi;
+use transport::mock::normalize_query;
+use transport::mock::MockDocker;
+use transport::parse::ResponseFutureWrapper;
+
+/// Exchanges with a docker daemon, in the order the requests were sent
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub api_version: Option<String>,
+    pub exchanges: Vec<Exchange>,
+}
+
+/// Request and the response it was answered with, `status` is missing when no response
+/// was received
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub status: Option<u16>,
+    pub headers: Vec<(String, String)>,
+    pub chunks: Vec<RecordedChunk>,
+}
+
+/// Body chunk, kept as text when it is valid UTF-8
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RecordedChunk {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl RecordedChunk {
+    fn new(bytes: &[u8]) -> RecordedChunk {
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(text) => RecordedChunk::Text(text),
+            Err(err) => RecordedChunk::Bytes(err.into_bytes()),
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        match self {
+            RecordedChunk::Text(text) => text.into_bytes(),
+            RecordedChunk::Bytes(bytes) => bytes,
+        }
+    }
+}
+
+impl Cassette {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Cassette> {
+        let content = fs::read(path)?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let content = serde_json::to_vec_pretty(self)?;
+        Ok(fs::write(path, content)?)
+    }
+
+    /// Creates a transport answering the recorded requests with the recorded responses,
+    /// keeping the chunk boundaries of streamed bodies
+    pub fn replay(&self) -> Result<MockDocker> {
+        let mut mock = MockDocker::new();
+
+        if let Some(ref version) = self.api_version {
+            mock.set_api_version(version.parse()?);
+        }
+
+        for exchange in &self.exchanges {
+            let status = match exchange.status {
+                Some(status) => StatusCode::from_u16(status)
+                    .map_err(|_| format!("Invalid recorded status {}", status))?,
+                None => continue,
+            };
+
+            let method = Method::from_str(&exchange.method)
+                .map_err(|_| format!("Invalid recorded method {}", exchange.method))?;
+
+            let expectation = mock.expect(method, &exchange.path);
+            expectation.query(&exchange.query).status(status);
+
+            for (name, value) in &exchange.headers {
+                expectation.header(HeaderName::from_str(name)?, HeaderValue::from_str(value)?);
+            }
+
+            for chunk in &exchange.chunks {
+                expectation.chunk(chunk.clone().into_bytes());
+            }
+        }
+
+        Ok(mock)
+    }
+}
+
+/// Transport forwarding the requests to another one and recording the exchanges into
+/// a cassette file, which is rewritten when a response arrives and when its body ends
+pub struct RecordingTransport {
+    inner: Arc<InteractApi>,
+    recorder: Arc<Recorder>,
+}
+
+struct Recorder {
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl Recorder {
+    fn update<F: FnOnce(&mut Exchange)>(&self, index: usize, f: F) {
+        let mut cassette = self.cassette.lock().expect("Cassette lock poisoned");
+        f(&mut cassette.exchanges[index]);
+    }
+
+    fn save(&self) -> Result<()> {
+        let cassette = self.cassette.lock().expect("Cassette lock poisoned");
+        cassette.save(&self.path)
+    }
+}
+
+impl RecordingTransport {
+    pub fn new<P: Into<PathBuf>>(inner: Arc<InteractApi>, path: P) -> RecordingTransport {
+        let cassette = Cassette {
+            api_version: inner.api_version().map(|v| v.to_string()),
+            exchanges: Vec::new(),
+        };
+
+        RecordingTransport {
+            inner,
+            recorder: Arc::new(Recorder {
+                path: path.into(),
+                cassette: Mutex::new(cassette),
+            }),
+        }
+    }
+
+    /// Writes the exchanges recorded so far, including the responses still being received
+    pub fn save(&self) -> Result<()> {
+        self.recorder.save()
+    }
+}
+
+impl InteractApi for RecordingTransport {
+    fn request(&self, opts: RequestArgs, method: Method) -> ResponseFutureWrapper {
+        let index = {
+            let mut cassette = self.recorder.cassette.lock().expect("Cassette lock poisoned");
+            cassette.exchanges.push(Exchange {
+                method: method.to_string(),
+                path: opts.path.to_string(),
+                query: normalize_query(opts.query),
+                status: None,
+                headers: Vec::new(),
+                chunks: Vec::new(),
+            });
+            cassette.exchanges.len() - 1
+        };
+
+        let recorder = self.recorder.clone();
+        let response = self.inner.request(opts, method).map(move |response| {
+            recorder.update(index, |exchange| {
+                exchange.status = Some(response.status().as_u16());
+                exchange.headers = response
+                    .headers()
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        value
+                            .to_str()
+                            .ok()
+                            .map(|value| (name.as_str().to_string(), value.to_string()))
+                    }).collect();
+            });
+
+            // Calls answered by their status only may never poll the body
+            if let Err(err) = recorder.save() {
+                warn!("Failed to save the cassette: {}", err);
+            }
+
+            // Upgraded connections carry raw traffic which can't be recorded
+            if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+                return response;
+            }
+
+            let (parts, body) = response.into_parts();
+            let body = RecordedBody {
+                body,
+                recorder,
+                index,
+            };
+
+            Response::from_parts(parts, Body::wrap_stream(body))
+        });
+
+        Box::new(response)
+    }
+
+    fn api_version(&self) -> Option<ApiVersion> {
+        self.inner.api_version()
+    }
+}
+
+struct RecordedBody {
+    body: Body,
+    recorder: Arc<Recorder>,
+    index: usize,
+}
+
+impl Stream for RecordedBody {
+    type Item = Chunk;
+    type Error = ::hyper::Error;
+
+    fn poll(&mut self) -> Poll<Option<Chunk>, ::hyper::Error> {
+        match self.body.poll()? {
+            Async::Ready(Some(chunk)) => {
+                self.recorder.update(self.index, |exchange| {
+                    exchange.chunks.push(RecordedChunk::new(&chunk))
+                });
+                Ok(Async::Ready(Some(chunk)))
+            }
+            Async::Ready(None) => {
+                if let Err(err) = self.recorder.save() {
+                    warn!("Failed to save the cassette: {}", err);
+                }
+                Ok(Async::Ready(None))
+            }
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cassette;
+    use super::RecordedChunk;
+    use super::RecordingTransport;
+    use communicate::new_docker_with_transport;
+    use futures::Future;
+    use futures::Stream;
+    use hyper::Method;
+    use hyper::StatusCode;
+    use std::env;
+    use std::fs;
+    use std::sync::Arc;
+    use transport::mock::MockDocker;
+    use ContainerListOptions;
+    use EventsOptions;
+
+    #[test]
+    fn record_and_replay() {
+        let name = format!("async_docker_cassette_{}.json", ::std::process::id());
+        let path = env::temp_dir().join(name);
+        let event = r#"{"status":"start","id":"abc","from":"alpine","time":1,"timeNano":1}"#;
+
+        let mut mock = MockDocker::new();
+        mock.expect(Method::GET, "/events")
+            .chunk(format!("{}\n{}", event, &event[..10]))
+            .chunk(format!("{}\n", &event[10..]));
+
+        let recording = RecordingTransport::new(Arc::new(mock), path.clone());
+        let docker = new_docker_with_transport(Arc::new(recording));
+        let recorded = docker
+            .events(&EventsOptions::default())
+            .collect()
+            .wait()
+            .expect("Events failed");
+
+        let cassette = Cassette::load(&path).expect("Cassette not saved");
+        fs::remove_file(&path).expect("Cassette removal failed");
+
+        let exchange = &cassette.exchanges[0];
+        assert_eq!(exchange.status, Some(200));
+        assert_eq!(
+            exchange.chunks,
+            vec![
+                RecordedChunk::Text(format!("{}\n{}", event, &event[..10])),
+                RecordedChunk::Text(format!("{}\n", &event[10..])),
+            ]
+        );
+        assert_eq!(RecordedChunk::new(&[0xff]), RecordedChunk::Bytes(vec![0xff]));
+
+        let replay = Arc::new(cassette.replay().expect("Replay failed"));
+        let docker = new_docker_with_transport(replay.clone());
+        let replayed = docker
+            .events(&EventsOptions::default())
+            .collect()
+            .wait()
+            .expect("Events failed");
+
+        assert_eq!(recorded.len(), replayed.len());
+        replay.verify();
+    }
+
+    #[test]
+    fn record_queries_and_status_only_calls() {
+        let name = format!("async_docker_cassette_status_{}.json", ::std::process::id());
+        let path = env::temp_dir().join(name);
+        let list = || {
+            ContainerListOptions::builder()
+                .all()
+                .sized()
+                .since("abc")
+                .build()
+        };
+
+        let mut mock = MockDocker::new();
+        mock.expect(Method::GET, "/containers/json")
+            .query("since=abc&size=true&all=true")
+            .body("[]");
+        mock.expect(Method::POST, "/containers/abc/start")
+            .status(StatusCode::NO_CONTENT);
+
+        let recording = RecordingTransport::new(Arc::new(mock), path.clone());
+        let docker = new_docker_with_transport(Arc::new(recording));
+        docker
+            .containers()
+            .list(&list())
+            .wait()
+            .expect("List failed");
+        docker
+            .container("abc".into())
+            .start()
+            .wait()
+            .expect("Start failed");
+
+        let cassette = Cassette::load(&path).expect("Cassette not saved");
+        fs::remove_file(&path).expect("Cassette removal failed");
+
+        assert_eq!(cassette.exchanges.len(), 2);
+        assert_eq!(cassette.exchanges[0].query, "all=true&since=abc&size=true");
+        assert_eq!(cassette.exchanges[1].status, Some(204));
+
+        let replay = Arc::new(cassette.replay().expect("Replay failed"));
+        let docker = new_docker_with_transport(replay.clone());
+        docker
+            .containers()
+            .list(&list())
+            .wait()
+            .expect("List failed");
+        let status = docker
+            .container("abc".into())
+            .start()
+            .wait()
+            .expect("Start failed");
+
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        replay.verify();
+    }
+}
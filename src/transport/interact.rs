@@ -1,10 +1,12 @@
 use communicate::util::IntoRequestArgs;
+use communicate::version::ApiVersion;
 use communicate::util::RequestArgs;
 use futures::future;
 use futures::Future;
 use http::header::HeaderValue;
 use http::header::CONNECTION;
 use http::header::CONTENT_TYPE;
+use http::header::UPGRADE;
 use hyper::client::connect::Connect;
 use hyper::Client;
 use hyper::Method;
@@ -14,14 +16,23 @@ use transport::parse::compose_uri;
 use transport::parse::ResponseFutureWrapper;
 use Error;
 
-pub(crate) trait InteractApi: Send + Sync {
+/// Transport sending the requests of the docker client, implemented over hyper for
+/// real daemons and by `MockDocker` for tests
+pub trait InteractApi: Send + Sync {
     fn request(&self, opts: RequestArgs, method: Method) -> ResponseFutureWrapper;
+
+    /// API version requests are pinned to, `None` lets the daemon pick its own
+    fn api_version(&self) -> Option<ApiVersion>;
 }
 
 impl InteractApi for Arc<InteractApi> {
     fn request(&self, opts: RequestArgs, method: Method) -> ResponseFutureWrapper {
         (**self).request(opts, method)
     }
+
+    fn api_version(&self) -> Option<ApiVersion> {
+        (**self).api_version()
+    }
 }
 
 pub(crate) trait InteractApiExt {
@@ -44,6 +55,14 @@ pub(crate) trait InteractApiExt {
     fn delete<'a, 'b, A>(&self, opts: A) -> ResponseFutureWrapper
     where
         A: IntoRequestArgs<'a, 'b>;
+
+    fn head<'a, 'b, A>(&self, opts: A) -> ResponseFutureWrapper
+    where
+        A: IntoRequestArgs<'a, 'b>;
+
+    fn post_upgrade<'a, 'b, A>(&self, opts: A) -> ResponseFutureWrapper
+    where
+        A: IntoRequestArgs<'a, 'b>;
 }
 
 impl<T> InteractApiExt for T
@@ -97,6 +116,36 @@ where
     {
         self.request(opts.into_request_args(), Method::DELETE)
     }
+
+    fn head<'a, 'b, A>(&self, opts: A) -> ResponseFutureWrapper
+    where
+        A: IntoRequestArgs<'a, 'b>,
+    {
+        self.request(opts.into_request_args(), Method::HEAD)
+    }
+
+    fn post_upgrade<'a, 'b, A>(&self, opts: A) -> ResponseFutureWrapper
+    where
+        A: IntoRequestArgs<'a, 'b>,
+    {
+        let mut opts = opts.into_request_args();
+        opts.set_header(
+            CONNECTION,
+            HeaderValue::from_str("Upgrade")
+                .expect("Constant connection header value's parse failed"),
+        );
+        opts.set_header(
+            UPGRADE,
+            HeaderValue::from_str("tcp").expect("Constant upgrade header value's parse failed"),
+        );
+        opts.set_header(
+            CONTENT_TYPE,
+            HeaderValue::from_str("application/json")
+                .expect("Constant content type header value's parse failed"),
+        );
+
+        self.request(opts, Method::POST)
+    }
 }
 
 #[derive(Clone)]
@@ -106,14 +155,19 @@ where
 {
     client: Client<I>,
     host: Uri,
+    version: Option<ApiVersion>,
 }
 
 impl<I> Interact<I>
 where
     I: Connect + 'static,
 {
-    pub fn new(client: Client<I>, host: Uri) -> Self {
-        Interact { client, host }
+    pub fn new(client: Client<I>, host: Uri, version: Option<ApiVersion>) -> Self {
+        Interact {
+            client,
+            host,
+            version,
+        }
     }
 }
 
@@ -123,7 +177,13 @@ where
 {
     fn request(&self, opts: RequestArgs, method: Method) -> ResponseFutureWrapper {
         let client = self.client.clone();
-        let uri_result = compose_uri(&self.host, opts.path, opts.query);
+        let uri_result = match self.version {
+            Some(version) => {
+                let path = format!("/v{}{}", version, opts.path);
+                compose_uri(&self.host, &path, opts.query)
+            }
+            None => compose_uri(&self.host, opts.path, opts.query),
+        };
 
         let b = opts.body;
         let h = opts.header;
@@ -140,7 +200,11 @@ where
                         request.headers_mut().insert(key, h.1);
                     }
                     Ok(request)
-                }).and_then(move |request| Ok(client.request(request))),
+                }).and_then(move |request| client.request(request).map_err(Error::from)),
         )
     }
+
+    fn api_version(&self) -> Option<ApiVersion> {
+        self.version
+    }
 }
@@ -53,25 +53,71 @@ where
     type Error = S::Error;
 
     fn poll(&mut self) -> Poll<Option<String>, S::Error> {
-        match self.stream.poll()? {
-            Async::NotReady => Ok(Async::NotReady),
-            Async::Ready(None) => match self.process(true) {
-                Some(Ok(line)) => Ok(Async::Ready(Some(line))),
-                Some(Err(err)) => Err(err.into()),
-                None => Ok(Async::Ready(None)),
-            },
-            Async::Ready(Some(chunk)) => {
-                if let Some(ref mut buffer) = self.buffered {
-                    buffer.extend(chunk.as_ref());
-                } else {
-                    self.buffered = Some(chunk.as_ref().to_vec());
+        loop {
+            // Lines left over from previous chunks come first
+            if let Some(line) = self.process(false) {
+                return line.map(|line| Async::Ready(Some(line))).map_err(From::from);
+            }
+
+            match self.stream.poll()? {
+                Async::NotReady => return Ok(Async::NotReady),
+                Async::Ready(None) => {
+                    // A trailing newline doesn't start another line
+                    if self.buffered.as_ref().map(|b| b.is_empty()).unwrap_or(true) {
+                        return Ok(Async::Ready(None));
+                    }
+
+                    return match self.process(true) {
+                        Some(Ok(line)) => Ok(Async::Ready(Some(line))),
+                        Some(Err(err)) => Err(err.into()),
+                        None => Ok(Async::Ready(None)),
+                    };
                 }
-                match self.process(false) {
-                    Some(Ok(line)) => Ok(Async::Ready(Some(line))),
-                    Some(Err(err)) => Err(err.into()),
-                    None => Ok(Async::NotReady),
+                Async::Ready(Some(chunk)) => {
+                    if let Some(ref mut buffer) = self.buffered {
+                        buffer.extend(chunk.as_ref());
+                    } else {
+                        self.buffered = Some(chunk.as_ref().to_vec());
+                    }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Lines;
+    use futures::future;
+    use futures::stream;
+    use futures::Async;
+    use futures::Future;
+    use futures::Stream;
+    use std::string::FromUtf8Error;
+
+    #[derive(Debug)]
+    struct Error;
+
+    impl From<FromUtf8Error> for Error {
+        fn from(_: FromUtf8Error) -> Error {
+            Error
+        }
+    }
+
+    #[test]
+    fn lines_across_chunks() {
+        let chunks = vec!["sta", "rt\nrun", "ning\nstop\nexit", "ed\n"];
+        let mut lines = Lines::new(stream::iter_ok::<_, Error>(chunks));
+
+        // A chunk without a newline used to be reported as not ready without
+        // scheduling another poll, stalling the stream forever
+        let first = future::lazy(|| Ok::<_, Error>(lines.poll())).wait();
+        match first.expect("Poll failed").expect("Line failed") {
+            Async::Ready(Some(line)) => assert_eq!(line, "start"),
+            other => panic!("Unexpected poll result: {:?}", other),
+        }
+
+        let rest = lines.collect().wait().expect("Lines failed");
+        assert_eq!(rest, vec!["running", "stop", "exited"]);
+    }
+}
@@ -0,0 +1,283 @@
+use futures::Async;
+use futures::Poll;
+use futures::Stream;
+use hyper::Chunk;
+use representation::rep::LogLine;
+use representation::rep::LogStream;
+use std::collections::VecDeque;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use Error;
+
+/// Reassembles `(stream_type, chunk)` frames into complete lines, keeping
+/// a separate buffer for every stream so interleaved frames don't mix
+struct LogLines<S>
+where
+    S: Stream<Item = (u32, Chunk), Error = Error>,
+{
+    inner: S,
+    timestamps: bool,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    ready: VecDeque<LogLine>,
+    done: bool,
+}
+
+impl<S> LogLines<S>
+where
+    S: Stream<Item = (u32, Chunk), Error = Error>,
+{
+    fn push(&mut self, stream: LogStream, chunk: &[u8]) {
+        let timestamps = self.timestamps;
+        let (buf, ready) = match stream {
+            LogStream::StdErr => (&mut self.stderr, &mut self.ready),
+            _ => (&mut self.stdout, &mut self.ready),
+        };
+
+        buf.extend_from_slice(chunk);
+        while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buf.drain(..pos + 1).collect();
+            ready.push_back(log_line(stream.clone(), &line[..pos], timestamps));
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.stdout.is_empty() {
+            let line = log_line(LogStream::StdOut, &self.stdout, self.timestamps);
+            self.ready.push_back(line);
+            self.stdout.clear();
+        }
+        if !self.stderr.is_empty() {
+            let line = log_line(LogStream::StdErr, &self.stderr, self.timestamps);
+            self.ready.push_back(line);
+            self.stderr.clear();
+        }
+    }
+}
+
+impl<S> Stream for LogLines<S>
+where
+    S: Stream<Item = (u32, Chunk), Error = Error>,
+{
+    type Item = LogLine;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        loop {
+            if let Some(line) = self.ready.pop_front() {
+                return Ok(Async::Ready(Some(line)));
+            }
+
+            if self.done {
+                return Ok(Async::Ready(None));
+            }
+
+            match self.inner.poll()? {
+                Async::NotReady => return Ok(Async::NotReady),
+                Async::Ready(Some((stream_type, chunk))) => {
+                    let stream = match stream_type {
+                        0 => LogStream::StdIn,
+                        2 => LogStream::StdErr,
+                        _ => LogStream::StdOut,
+                    };
+                    self.push(stream, &chunk);
+                }
+                Async::Ready(None) => {
+                    self.done = true;
+                    self.flush();
+                }
+            }
+        }
+    }
+}
+
+/// Turns a stream of decoded frames into a stream of typed log lines.
+/// With `timestamps` set every line is expected to start with the
+/// RFC3339 timestamp added by the docker daemon.
+pub fn lines<S>(frames: S, timestamps: bool) -> impl Stream<Item = LogLine, Error = Error>
+where
+    S: Stream<Item = (u32, Chunk), Error = Error>,
+{
+    LogLines {
+        inner: frames,
+        timestamps,
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        ready: VecDeque::new(),
+        done: false,
+    }
+}
+
+fn log_line(stream: LogStream, line: &[u8], timestamps: bool) -> LogLine {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim_end_matches('\r');
+
+    let (timestamp, message) = if timestamps {
+        match line.find(' ') {
+            Some(pos) => match parse_timestamp(&line[..pos]) {
+                Some(ts) => (Some(ts), &line[pos + 1..]),
+                None => (None, line),
+            },
+            None => match parse_timestamp(line) {
+                Some(ts) => (Some(ts), ""),
+                None => (None, line),
+            },
+        }
+    } else {
+        (None, line)
+    };
+
+    LogLine {
+        stream,
+        timestamp,
+        message: message.to_owned(),
+    }
+}
+
+/// Parses an RFC3339 timestamp, as emitted by the docker daemon, into a `SystemTime`
+pub(crate) fn parse_timestamp(ts: &str) -> Option<SystemTime> {
+    fn number(s: &str) -> Option<i64> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            None
+        } else {
+            s.parse().ok()
+        }
+    }
+
+    if ts.len() < 20 || !ts.is_char_boundary(19) {
+        return None;
+    }
+
+    let (date_time, rest) = ts.split_at(19);
+    let bytes = date_time.as_bytes();
+    if bytes[4] != b'-' || bytes[7] != b'-' || (bytes[10] != b'T' && bytes[10] != b't') {
+        return None;
+    }
+    if bytes[13] != b':' || bytes[16] != b':' {
+        return None;
+    }
+
+    let year = number(&date_time[0..4])?;
+    let month = number(&date_time[5..7])?;
+    let day = number(&date_time[8..10])?;
+    let hour = number(&date_time[11..13])?;
+    let minute = number(&date_time[14..16])?;
+    let second = number(&date_time[17..19])?;
+
+    let valid = (1..=12).contains(&month)
+        && (1..=31).contains(&day)
+        && hour <= 23
+        && minute <= 59
+        && second <= 60;
+    if !valid {
+        return None;
+    }
+
+    let (nanos, zone) = if let Some(fractional) = rest.strip_prefix('.') {
+        let end = fractional
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(fractional.len());
+        let fraction = &fractional[..end];
+        number(fraction)?;
+
+        let mut digits: String = fraction.chars().take(9).collect();
+        while digits.len() < 9 {
+            digits.push('0');
+        }
+        (number(&digits)?, &fractional[end..])
+    } else {
+        (0, rest)
+    };
+
+    let offset = match zone {
+        "Z" | "z" => 0,
+        // Checked as ASCII first, slicing through a multi-byte character would panic
+        _ if zone.len() == 6 && zone.is_ascii() && &zone[3..4] == ":" => {
+            let sign = match &zone[0..1] {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            sign * (number(&zone[1..3])? * 3600 + number(&zone[4..6])? * 60)
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second - offset;
+
+    if secs >= 0 {
+        Some(UNIX_EPOCH + Duration::new(secs as u64, nanos as u32))
+    } else {
+        None
+    }
+}
+
+/// Number of days since 1970-01-01 in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lines;
+    use super::parse_timestamp;
+    use futures::stream;
+    use futures::Future;
+    use futures::Stream;
+    use hyper::Chunk;
+    use representation::rep::LogStream;
+    use std::time::Duration;
+    use std::time::UNIX_EPOCH;
+    use Error;
+
+    #[test]
+    fn timestamp_utc() {
+        assert_eq!(
+            parse_timestamp("2019-03-15T10:20:30.123456789Z"),
+            Some(UNIX_EPOCH + Duration::new(1552645230, 123456789))
+        );
+    }
+
+    #[test]
+    fn timestamp_offset() {
+        assert_eq!(
+            parse_timestamp("2019-03-15T12:20:30+02:00"),
+            Some(UNIX_EPOCH + Duration::new(1552645230, 0))
+        );
+        assert_eq!(parse_timestamp("2019-03-15 12:20:30"), None);
+        assert_eq!(parse_timestamp("2019-03-15T10:20:30€:00"), None);
+        assert_eq!(parse_timestamp("2019-03-15T10:20:30.5+0€:0"), None);
+    }
+
+    #[test]
+    fn lines_split_across_frames() {
+        let frames = vec![
+            (1, Chunk::from("2019-03-15T10:20:30Z hel")),
+            (2, Chunk::from("2019-03-15T10:20:31Z oops\n")),
+            (1, Chunk::from("lo\n2019-03-15T10:20:32Z tail")),
+        ];
+        let frames = stream::iter_ok::<_, Error>(frames);
+
+        let lines = lines(frames, true).collect().wait().expect("Error during decoding");
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].stream, LogStream::StdErr);
+        assert_eq!(lines[0].message, "oops");
+        assert_eq!(lines[1].stream, LogStream::StdOut);
+        assert_eq!(lines[1].message, "hello");
+        assert_eq!(
+            lines[1].timestamp,
+            Some(UNIX_EPOCH + Duration::new(1552645230, 0))
+        );
+        assert_eq!(lines[2].message, "tail");
+    }
+}
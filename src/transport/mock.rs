@@ -0,0 +1,377 @@
+//! In-memory transport answering requests with canned responses, for tests without a daemon
+
+use communicate::util::RequestArgs;
+use communicate::version::ApiVersion;
+use futures::stream;
+use futures::Future;
+use futures::Stream;
+use http::header::HeaderName;
+use http::header::HeaderValue;
+use http::header::CONTENT_TYPE;
+use hyper::Body;
+use hyper::HeaderMap;
+use hyper::Method;
+use hyper::Response;
+use hyper::StatusCode;
+use serde::Serialize;
+use serde_json;
+use std::io;
+use std::sync::Arc;
+use std::sync::Mutex;
+use transport::interact::InteractApi;
+use transport::parse::ResponseFutureWrapper;
+use url::form_urlencoded;
+
+/// Transport replying to the expected requests registered with `expect`, any other
+/// request panics
+///
+/// ```rust,no_run
+/// # extern crate async_docker;
+/// # extern crate hyper;
+/// use async_docker::{new_docker_with_transport, MockDocker};
+/// use hyper::{Method, StatusCode};
+/// use std::sync::Arc;
+///
+/// # fn main() {
+/// let mut mock = MockDocker::new();
+/// mock.expect(Method::GET, "/version")
+///     .status(StatusCode::OK)
+///     .body(r#"{"ApiVersion":"1.37"}"#);
+///
+/// let mock = Arc::new(mock);
+/// let docker = new_docker_with_transport(mock.clone());
+/// // ... drive `docker.version()` on a runtime
+/// mock.verify();
+/// # }
+/// ```
+#[derive(Default)]
+pub struct MockDocker {
+    expectations: Mutex<Vec<Expectation>>,
+    bodies: Arc<Mutex<Vec<Vec<u8>>>>,
+    version: Option<ApiVersion>,
+}
+
+/// Request expected by `MockDocker` and the response it is answered with
+pub struct Expectation {
+    method: Method,
+    path: String,
+    query: Option<String>,
+    times: usize,
+    status: StatusCode,
+    headers: HeaderMap,
+    chunks: Vec<Vec<u8>>,
+}
+
+impl MockDocker {
+    pub fn new() -> MockDocker {
+        MockDocker {
+            ..Default::default()
+        }
+    }
+
+    /// API version reported to the client, checked by options requiring a newer one
+    pub fn set_api_version(&mut self, version: ApiVersion) -> &mut MockDocker {
+        self.version = Some(version);
+        self
+    }
+
+    /// Registers a request expected once, answered with an empty `200 OK` by default
+    pub fn expect(&mut self, method: Method, path: &str) -> &mut Expectation {
+        let expectations = self
+            .expectations
+            .get_mut()
+            .expect("Mock expectations lock poisoned");
+
+        expectations.push(Expectation::new(method, path));
+        expectations.last_mut().expect("Expectation just pushed")
+    }
+
+    /// Bodies of the requests received so far, in the order they were read
+    pub fn bodies(&self) -> Vec<Vec<u8>> {
+        self.bodies
+            .lock()
+            .expect("Mock bodies lock poisoned")
+            .clone()
+    }
+
+    /// Panics if any expected request wasn't received
+    pub fn verify(&self) {
+        let expectations = self
+            .expectations
+            .lock()
+            .expect("Mock expectations lock poisoned");
+
+        let missing = expectations
+            .iter()
+            .filter(|e| e.times > 0)
+            .map(|e| format!("{} {}{}", e.method, e.path, e.query_suffix()))
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            panic!("Expected requests not received: {}", missing.join(", "));
+        }
+    }
+}
+
+impl InteractApi for MockDocker {
+    fn request(&self, opts: RequestArgs, method: Method) -> ResponseFutureWrapper {
+        let mut expectations = self
+            .expectations
+            .lock()
+            .expect("Mock expectations lock poisoned");
+
+        let response = match expectations
+            .iter_mut()
+            .find(|e| e.times > 0 && e.matches(&method, opts.path, opts.query))
+        {
+            Some(expectation) => {
+                expectation.times -= 1;
+                expectation.response()
+            }
+            None => {
+                let query = if opts.query.is_empty() { "" } else { "?" };
+                panic!("Unexpected request: {} {}{}{}", method, opts.path, query, opts.query)
+            }
+        };
+
+        // Read before answering, as a daemon would
+        let bodies = self.bodies.clone();
+        let response = opts.body.concat2().from_err().map(move |body| {
+            bodies
+                .lock()
+                .expect("Mock bodies lock poisoned")
+                .push(body.to_vec());
+            response
+        });
+
+        Box::new(response)
+    }
+
+    fn api_version(&self) -> Option<ApiVersion> {
+        self.version
+    }
+}
+
+/// Sorts the parameters of a query string and the keys of its `filters` object, which
+/// are both serialized from hash maps in no particular order
+pub(crate) fn normalize_query(query: &str) -> String {
+    let mut pairs: Vec<String> = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(normalize_filters)
+        .collect();
+    pairs.sort();
+    pairs.join("&")
+}
+
+/// Re-serializes a `filters` parameter with its keys sorted, other parameters are kept
+/// as they are
+fn normalize_filters(pair: &str) -> String {
+    let filters = match form_urlencoded::parse(pair.as_bytes()).pop() {
+        Some((ref key, ref value)) if key == "filters" => {
+            serde_json::from_str::<serde_json::Value>(value).ok()
+        }
+        _ => None,
+    };
+
+    filters
+        .map(|filters| form_urlencoded::serialize(&[("filters", filters.to_string())]))
+        .unwrap_or_else(|| pair.to_string())
+}
+
+impl Expectation {
+    fn new(method: Method, path: &str) -> Expectation {
+        Expectation {
+            method,
+            path: path.to_string(),
+            query: None,
+            times: 1,
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            chunks: Vec::new(),
+        }
+    }
+
+    /// Query string the request has to carry, in any parameter order, any query matches
+    /// when not set
+    pub fn query(&mut self, query: &str) -> &mut Expectation {
+        self.query = Some(normalize_query(query));
+        self
+    }
+
+    /// Number of requests answered by this expectation
+    pub fn times(&mut self, times: usize) -> &mut Expectation {
+        self.times = times;
+        self
+    }
+
+    pub fn status(&mut self, status: StatusCode) -> &mut Expectation {
+        self.status = status;
+        self
+    }
+
+    pub fn header(&mut self, name: HeaderName, value: HeaderValue) -> &mut Expectation {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Replaces the response body
+    pub fn body<B: Into<Vec<u8>>>(&mut self, body: B) -> &mut Expectation {
+        self.chunks = vec![body.into()];
+        self
+    }
+
+    /// Replaces the response body with the serialized value
+    pub fn json<T: Serialize>(&mut self, value: &T) -> &mut Expectation {
+        let body = serde_json::to_vec(value).expect("Mock body serialization failed");
+        self.header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .body(body)
+    }
+
+    /// Appends a chunk to the response body, which is streamed chunk by chunk
+    /// like the events or stats endpoints
+    pub fn chunk<B: Into<Vec<u8>>>(&mut self, chunk: B) -> &mut Expectation {
+        self.chunks.push(chunk.into());
+        self
+    }
+
+    fn matches(&self, method: &Method, path: &str, query: &str) -> bool {
+        self.method == *method
+            && self.path == path
+            && self
+                .query
+                .as_ref()
+                .map(|q| *q == normalize_query(query))
+                .unwrap_or(true)
+    }
+
+    fn query_suffix(&self) -> String {
+        self.query
+            .as_ref()
+            .map(|q| format!("?{}", q))
+            .unwrap_or_default()
+    }
+
+    fn response(&self) -> Response<Body> {
+        let chunks = stream::iter_ok::<_, io::Error>(self.chunks.clone());
+        let mut response = Response::new(Body::wrap_stream(chunks));
+
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_query;
+    use super::MockDocker;
+    use communicate::new_docker_with_transport;
+    use futures::Future;
+    use futures::Stream;
+    use hyper::Method;
+    use hyper::StatusCode;
+    use std::borrow::Cow;
+    use std::sync::Arc;
+    use url::form_urlencoded;
+    use ContainerFilter;
+    use ContainerListOptions;
+    use ErrorKind;
+    use EventsOptions;
+
+    #[test]
+    fn mock_responses() {
+        let mut mock = MockDocker::new();
+        mock.expect(Method::GET, "/version")
+            .body(r#"{"ApiVersion":"1.37","Version":"18.03.1-ce","GitCommit":"9ee9f40","GoVersion":"go1.9.5","Os":"linux","Arch":"amd64","KernelVersion":"4.15.0"}"#);
+        mock.expect(Method::GET, "/containers/missing/json")
+            .status(StatusCode::NOT_FOUND)
+            .body(r#"{"message":"No such container: missing"}"#);
+
+        let mock = Arc::new(mock);
+        let docker = new_docker_with_transport(mock.clone());
+
+        let version = docker.version().wait().expect("Version failed");
+        assert_eq!(version.ApiVersion, "1.37");
+
+        let err = docker
+            .container(Cow::from("missing"))
+            .inspect()
+            .wait()
+            .expect_err("Inspect succeeded");
+        match *err.kind() {
+            ErrorKind::NotFound(_, ref message) => {
+                assert_eq!(message, "No such container: missing")
+            }
+            ref kind => panic!("Unexpected error: {:?}", kind),
+        }
+
+        mock.verify();
+    }
+
+    #[test]
+    fn mock_streamed_body() {
+        let event = r#"{"status":"start","id":"abc","from":"alpine","time":1,"timeNano":1}"#;
+
+        let mut mock = MockDocker::new();
+        mock.expect(Method::GET, "/events")
+            .chunk(format!("{}\n", event))
+            .chunk(format!("{}\n", event));
+
+        let docker = new_docker_with_transport(Arc::new(mock));
+        let events = docker
+            .events(&EventsOptions::default())
+            .collect()
+            .wait()
+            .expect("Events failed");
+
+        assert_eq!(events.len(), 2);
+        let event = events[0].as_ref().expect("Invalid event");
+        assert_eq!(event.status.as_deref(), Some("start"));
+    }
+
+    #[test]
+    fn mock_query_filters_in_any_order() {
+        let labels = r#"{"label":["app"],"status":["running"]}"#;
+        let statuses = r#"{"status":["running"],"label":["app"]}"#;
+        let query = |filters| form_urlencoded::serialize(&[("all", "true"), ("filters", filters)]);
+        assert_eq!(
+            normalize_query(&query(labels)),
+            normalize_query(&query(statuses))
+        );
+
+        let mut mock = MockDocker::new();
+        mock.expect(Method::GET, "/containers/json")
+            .query(&query(statuses))
+            .body("[]");
+
+        let mock = Arc::new(mock);
+        let docker = new_docker_with_transport(mock.clone());
+        let opts = ContainerListOptions::builder()
+            .all()
+            .filter(vec![
+                ContainerFilter::LabelName("app".to_string()),
+                ContainerFilter::Status("running".to_string()),
+            ]).build();
+
+        let containers = docker.containers().list(&opts).wait().expect("List failed");
+        assert!(containers.is_empty());
+        mock.verify();
+    }
+
+    #[test]
+    #[should_panic(expected = "Unexpected request: GET /info")]
+    fn mock_unexpected_request() {
+        let docker = new_docker_with_transport(Arc::new(MockDocker::new()));
+        let _ = docker.info();
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected requests not received: GET /info")]
+    fn mock_missing_request() {
+        let mut mock = MockDocker::new();
+        mock.expect(Method::GET, "/info");
+        mock.verify();
+    }
+}
@@ -1,6 +1,10 @@
+pub mod cassette;
 pub mod interact;
 mod lines;
+pub mod logs;
+pub mod mock;
 pub mod parse;
+pub(crate) mod rustls_connector;
 pub mod tty;
 
 pub use self::parse::*;
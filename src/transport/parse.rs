@@ -3,33 +3,42 @@ extern crate tokio_codec;
 
 use Result;
 
-use hyper::client::ResponseFuture;
 use hyper::rt::Future;
 use hyper::Body;
 use hyper::Method;
 use hyper::Request;
+use hyper::upgrade::Upgraded;
+use hyper::Response;
 use hyper::Uri;
 use std::convert::Into;
 
 use self::tokio_codec::BytesCodec;
 use self::tokio_codec::FramedWrite;
 use super::lines::Lines;
+use base64;
 use bytes::Bytes;
 use errors::Error;
+use errors::ErrorKind;
 use futures::future;
+use futures::future::Either;
 use futures::Sink;
 use futures::Stream;
 use http::uri::PathAndQuery;
 use http::StatusCode;
 use hyper::Chunk;
+use representation::rep::BuildOutput;
+use representation::rep::PullProgress;
+use serde_json::from_slice as de_from_slice;
 use serde_json::from_str as de_from_str;
+use serde_json::Value;
 use std::fmt::Debug;
 use std::path::Path;
 use std::str;
 use std::str::FromStr;
 use tokio::fs::File;
 
-pub type ResponseFutureWrapper = Box<Future<Item = ResponseFuture, Error = Error> + Send>;
+/// Response of a request sent by an `InteractApi` transport
+pub type ResponseFutureWrapper = Box<Future<Item = Response<Body>, Error = Error> + Send>;
 
 pub(crate) fn build_request<B>(method: Method, uri: Uri, body: B) -> Result<Request<Body>>
 where
@@ -44,15 +53,39 @@ where
         .map_err(Error::from)
 }
 
+/// Resolves the response and turns any non-successful status into an `Error`
+/// carrying the message reported by the docker daemon
+pub(crate) fn checked_response(
+    future: ResponseFutureWrapper,
+) -> impl Future<Item = Response<Body>, Error = Error> + Send {
+    future.and_then(check_status)
+}
+
+pub(crate) fn check_status(
+    response: Response<Body>,
+) -> impl Future<Item = Response<Body>, Error = Error> + Send {
+    let status = response.status();
+
+    if status.is_success() || status == StatusCode::SWITCHING_PROTOCOLS {
+        Either::A(future::ok(response))
+    } else {
+        Either::B(
+            response
+                .into_body()
+                .concat2()
+                .map_err(Error::from)
+                .and_then(move |chunk| Err(status_error(status, chunk.as_ref()))),
+        )
+    }
+}
+
 pub(crate) fn status_code(
     future: ResponseFutureWrapper,
 ) -> impl Future<Item = StatusCode, Error = Error> + Send {
-    future
-        .and_then(|w| w.map_err(Error::from))
-        .and_then(|response| {
-            debug!("GET");
-            future::ok(response.status())
-        }).map_err(Error::from)
+    checked_response(future).and_then(|response| {
+        debug!("GET");
+        future::ok(response.status())
+    })
 }
 
 pub(crate) fn parse_to_trait<T>(
@@ -61,31 +94,51 @@ pub(crate) fn parse_to_trait<T>(
 where
     T: for<'a> ::serde::Deserialize<'a> + Send + 'static,
 {
-    future.and_then(|w| {
-        w.and_then(|response| response.into_body().concat2())
-            .map_err(Error::from)
-            .and_then(|chunk| {
-                de_from_str::<T>(str::from_utf8(chunk.as_ref())?).map_err(Error::from)
-            })
+    checked_response(future)
+        .and_then(|response| response.into_body().concat2().map_err(Error::from))
+        .and_then(|chunk| de_from_str::<T>(str::from_utf8(chunk.as_ref())?).map_err(Error::from))
+}
+
+/// Resolves the JSON value carried base64 encoded by a response header
+pub(crate) fn parse_to_header<T>(
+    future: ResponseFutureWrapper,
+    name: &'static str,
+) -> impl Future<Item = T, Error = Error> + Send
+where
+    T: for<'a> ::serde::Deserialize<'a> + Send + 'static,
+{
+    checked_response(future).and_then(move |response| {
+        let value = response
+            .headers()
+            .get(name)
+            .ok_or(ErrorKind::HeaderMissing(name))?;
+        let json = base64::decode(value.as_bytes())?;
+
+        Ok(de_from_slice::<T>(&json)?)
     })
 }
 
+pub(crate) fn parse_to_body(
+    future: ResponseFutureWrapper,
+) -> impl Stream<Item = Chunk, Error = Error> {
+    checked_response(future)
+        .and_then(|response| Ok(response.into_body().map_err(Error::from)))
+        .flatten_stream()
+}
+
 pub(crate) fn parse_to_lines(
     future: ResponseFutureWrapper,
 ) -> impl Stream<Item = String, Error = Error> {
-    future
-        .and_then(|w| {
-            w.map_err(Error::from)
-                .and_then(|response| {
-                    let body = response
-                        .into_body()
-                        .map_err(Error::from)
-                        .map({ |a| a.into_bytes().clone() });
-
-                    let lines = Lines::new(body);
-
-                    Ok(lines)
-                }).map_err(Error::from)
+    checked_response(future)
+        .and_then(|response| {
+            let body = response
+                .into_body()
+                .map_err(Error::from)
+                .map({ |a| a.into_bytes().clone() });
+
+            let lines = Lines::new(body);
+
+            Ok(lines)
         }).flatten_stream()
 }
 
@@ -95,41 +148,80 @@ pub(crate) fn parse_to_stream<T>(
 where
     T: for<'a> ::serde::Deserialize<'a> + Send + Debug + 'static,
 {
-    future
-        .and_then(|w| {
-            w.map_err(Error::from)
-                .and_then(|response| {
-                    let body = response
-                        .into_body()
-                        .map_err(Error::from)
-                        .map({ |a| a.into_bytes().clone() });
-
-                    let lines = Lines::new(body);
-
-                    let mapped = lines.map(|chunk| {
-                        let as_str = str::from_utf8(chunk.as_ref())?;
-                        let t = de_from_str::<T>(as_str).map_err(Error::from);
-                        t
-                    });
-
-                    Ok(mapped)
-                }).map_err(Error::from)
+    checked_response(future)
+        .and_then(|response| {
+            let body = response
+                .into_body()
+                .map_err(Error::from)
+                .map({ |a| a.into_bytes().clone() });
+
+            let lines = Lines::new(body);
+
+            let mapped = lines.map(|chunk| {
+                let as_str = str::from_utf8(chunk.as_ref())?;
+                let t = de_from_str::<T>(as_str).map_err(Error::from);
+                t
+            });
+
+            Ok(mapped)
         }).flatten_stream()
 }
 
+/// Message of a streamed response which may report a failure in its body, after the
+/// response status was already sent
+pub(crate) trait StreamMessage {
+    fn error_message(&self) -> Option<String>;
+}
+
+impl StreamMessage for PullProgress {
+    fn error_message(&self) -> Option<String> {
+        self.errorDetail
+            .as_ref()
+            .and_then(|detail| detail.message.clone())
+            .or_else(|| self.error.clone())
+    }
+}
+
+impl StreamMessage for BuildOutput {
+    fn error_message(&self) -> Option<String> {
+        self.errorDetail
+            .as_ref()
+            .and_then(|detail| detail.message.clone())
+            .or_else(|| self.error.clone())
+    }
+}
+
+/// Same as `parse_to_stream`, but fails the stream with `ErrorKind::StreamError` on
+/// the first message reporting an error
+pub(crate) fn parse_to_checked_stream<T>(
+    future: ResponseFutureWrapper,
+) -> impl Stream<Item = T, Error = Error>
+where
+    T: for<'a> ::serde::Deserialize<'a> + StreamMessage + Send + Debug + 'static,
+{
+    parse_to_stream::<T>(future).and_then(|message| {
+        let message = message?;
+        match message.error_message() {
+            Some(error) => Err(ErrorKind::StreamError(error).into()),
+            None => Ok(message),
+        }
+    })
+}
+
+/// Resolves the connection handed over by the daemon after an `Upgrade: tcp` request
+pub(crate) fn parse_to_upgraded(
+    future: ResponseFutureWrapper,
+) -> impl Future<Item = Upgraded, Error = Error> + Send {
+    checked_response(future)
+        .and_then(|response| response.into_body().on_upgrade().map_err(Error::from))
+}
+
 #[allow(dead_code)]
 pub(crate) fn parse_to_file(
     future: ResponseFutureWrapper,
     filepath: &'static str,
 ) -> impl Future<Item = (), Error = Error> {
-    let stream = future
-        .and_then(|w| {
-            w.map_err(Error::from).and_then(|response| {
-                let body = response.into_body().map_err(Error::from);
-
-                Ok(body)
-            })
-        }).flatten_stream();
+    let stream = parse_to_body(future);
 
     let file = File::create(Path::new(filepath));
 
@@ -156,23 +248,74 @@ pub(crate) fn compose_uri(host: &Uri, path: &str, query: &str) -> Result<Uri> {
     Ok(Uri::from(res?))
 }
 
-/*
-/// Extract the error message content from an HTTP response that
+/// Extract the error message content from an HTTP response body that
 /// contains a Docker JSON error structure.
-#[allow(dead_code)]
-fn get_error_message(res: &mut Response) -> Option<String> {
-    let mut output = String::new();
+fn get_error_message(body: &[u8]) -> Option<String> {
+    de_from_slice::<Value>(body)
+        .ok()
+        .as_ref()
+        .and_then(|x| x.as_object())
+        .and_then(|x| x.get("message"))
+        .and_then(|x| x.as_str())
+        .map(|x| x.to_owned())
+}
 
-    if res.read_to_string(&mut output).is_ok() {
-        json::Json::from_str(output.as_str())
-            .ok()
-            .as_ref()
-            .and_then(|x| x.as_object())
-            .and_then(|x| x.get("message"))
-            .and_then(|x| x.as_string())
-            .map(|x| x.to_owned())
-    } else {
-        None
+/// Maps an unsuccessful response status to the matching `ErrorKind`
+pub(crate) fn status_error(status: StatusCode, body: &[u8]) -> Error {
+    let message = get_error_message(body)
+        .unwrap_or_else(|| String::from_utf8_lossy(body).trim().to_string());
+
+    let kind = match status {
+        StatusCode::NOT_MODIFIED => ErrorKind::NotModified(status, message),
+        StatusCode::BAD_REQUEST => ErrorKind::BadParameter(status, message),
+        StatusCode::NOT_FOUND => ErrorKind::NotFound(status, message),
+        StatusCode::CONFLICT => ErrorKind::Conflict(status, message),
+        s if s.is_server_error() => ErrorKind::ServerError(status, message),
+        _ => ErrorKind::UnexpectedStatus(status, message),
+    };
+
+    kind.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::status_error;
+    use errors::ErrorKind;
+    use http::StatusCode;
+
+    #[test]
+    fn status_error_not_found() {
+        let body = br#"{"message":"No such container: foo"}"#;
+
+        match status_error(StatusCode::NOT_FOUND, body).kind() {
+            ErrorKind::NotFound(status, message) => {
+                assert_eq!(*status, StatusCode::NOT_FOUND);
+                assert_eq!(message, "No such container: foo");
+            }
+            kind => panic!("Unexpected error kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn status_error_server_error() {
+        let body = br#"{"message":"driver failed"}"#;
+
+        match status_error(StatusCode::SERVICE_UNAVAILABLE, body).kind() {
+            ErrorKind::ServerError(status, message) => {
+                assert_eq!(*status, StatusCode::SERVICE_UNAVAILABLE);
+                assert_eq!(message, "driver failed");
+            }
+            kind => panic!("Unexpected error kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn status_error_plain_body() {
+        let body = b"page not found\n";
+
+        match status_error(StatusCode::CONFLICT, body).kind() {
+            ErrorKind::Conflict(_, message) => assert_eq!(message, "page not found"),
+            kind => panic!("Unexpected error kind: {:?}", kind),
+        }
     }
 }
-*/
@@ -0,0 +1,101 @@
+#![cfg(feature = "rustls")]
+
+use futures::Future;
+use futures_rustls::client;
+use futures_rustls::TlsConnector;
+use futures_util::compat::Compat;
+use futures_util::compat::Compat01As03;
+use futures_util::TryFutureExt;
+use hyper::client::connect::Connect;
+use hyper::client::connect::Connected;
+use hyper::client::connect::Destination;
+use hyper::client::HttpConnector;
+use rustls::ClientConfig;
+use rustls::ServerName;
+use std::convert::TryFrom;
+use std::io;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+
+/// TLS session over a tcp stream, driven by futures-rustls through the futures 0.1
+/// compatibility layer expected by hyper
+pub(crate) type TlsStream = Compat<client::TlsStream<Compat01As03<TcpStream>>>;
+
+/// Connector establishing TLS sessions with rustls on top of `HttpConnector`
+pub(crate) struct RustlsConnector {
+    http: HttpConnector,
+    tls: TlsConnector,
+    server_name: Option<String>,
+}
+
+impl RustlsConnector {
+    pub(crate) fn new(
+        http: HttpConnector,
+        config: ClientConfig,
+        server_name: Option<String>,
+    ) -> RustlsConnector {
+        RustlsConnector {
+            http,
+            tls: TlsConnector::from(Arc::new(config)),
+            server_name,
+        }
+    }
+}
+
+impl Connect for RustlsConnector {
+    type Transport = TlsStream;
+    type Error = io::Error;
+    type Future = Box<Future<Item = (TlsStream, Connected), Error = io::Error> + Send>;
+
+    fn connect(&self, dst: Destination) -> Self::Future {
+        let tls = self.tls.clone();
+        let name = self
+            .server_name
+            .clone()
+            .unwrap_or_else(|| dst.host().to_string());
+
+        let future = self
+            .http
+            .connect(dst)
+            .and_then(move |(tcp, connected)| {
+                let handshake = tls.connect(server_name(&name)?, Compat01As03::new(tcp));
+                Ok(handshake
+                    .compat()
+                    .map(move |stream| (Compat::new(stream), connected)))
+            }).flatten();
+
+        Box::new(future)
+    }
+}
+
+/// Name the daemon certificate is verified against, either a DNS name or an IP address
+/// which may be within brackets
+fn server_name(host: &str) -> io::Result<ServerName> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    ServerName::try_from(host).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid TLS server name '{}'", host),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::server_name;
+    use rustls::ServerName;
+
+    #[test]
+    fn server_names() {
+        let ip = |name: &str| match server_name(name) {
+            Ok(ServerName::IpAddress(addr)) => Some(addr.to_string()),
+            _ => None,
+        };
+
+        assert_eq!(ip("192.168.99.100"), Some("192.168.99.100".to_string()));
+        assert_eq!(ip("[::1]"), Some("::1".to_string()));
+        assert!(server_name("docker.example.com").is_ok());
+        assert!(server_name("not a name").is_err());
+    }
+}
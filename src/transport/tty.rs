@@ -5,11 +5,22 @@ use errors::ErrorKind;
 use futures::Async;
 use futures::Poll;
 use futures::Stream;
+use hyper::upgrade::Upgraded;
 use hyper::Chunk;
 use std::collections::VecDeque;
 use std::io::Cursor;
+use tokio::io::AsyncRead;
+use tokio::io::WriteHalf;
+use tokio_codec::BytesCodec;
+use tokio_codec::FramedRead;
 use Error;
 
+/// Writable stdin half of a hijacked connection
+pub type Stdin = WriteHalf<Upgraded>;
+
+/// Output half of a hijacked connection, yielding `(stream_type, chunk)` pairs
+pub type Output = Box<Stream<Item = (u32, Chunk), Error = Error> + Send>;
+
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 enum State {
     Header,
@@ -117,3 +128,20 @@ where
         inner: stream,
     }
 }
+
+/// Splits a hijacked connection into its stdin half and its output stream.
+/// Output of a TTY session isn't multiplexed, so all of it is reported as stdout.
+pub fn split(upgraded: Upgraded, tty: bool) -> (Stdin, Output) {
+    let (read, write) = upgraded.split();
+    let chunks = FramedRead::new(read, BytesCodec::new())
+        .map(|bytes| Chunk::from(bytes.freeze()))
+        .map_err(Error::from);
+
+    let output: Output = if tty {
+        Box::new(chunks.map(|chunk| (1, chunk)))
+    } else {
+        Box::new(decode(chunks))
+    };
+
+    (write, output)
+}